@@ -0,0 +1,565 @@
+#![allow(clippy::needless_return)] // Explicit returns are the house style
+
+// Headless front end for scripting surveys, e.g. over SSH on a Raspberry Pi
+//
+//   gator-cli interfaces [--json]
+//   gator-cli scan [--interface IF] [--json]
+//   gator-cli beacons (--pcap FILE | --monitor SECS --interface IF) [--json]
+//   gator-cli measure (--point x,y[,z] | --latlon LAT,LON | --gps) [--session FILE] [--samples N] [--interval MS] [--json]
+//   gator-cli measure ... (--pcap FILE | --monitor SECS --interface IF) --bssid MAC [--tx-power DBM]
+//   gator-cli solve [--session FILE] [--exponent N] [--unit m|ft] [--json]
+//   gator-cli agent --point x,y[,z] --id NAME [--collector HOST:PORT] [--interval MS] [--simulate RSSI[,TX]]
+//   gator-cli collect [--port N] [--exponent N] [--json]
+//   gator-cli calibrate --reference DBM [--gain DBI] [--name NAME] [--samples N] [--interval MS] [--json]
+
+use std::env;
+use std::path::PathBuf;
+use std::time::Instant;
+use std::process::ExitCode;
+use std::sync::atomic::AtomicBool;
+use std::{thread, time};
+
+use serde_json::json;
+use tracing::{info, warn};
+
+use triangle_gator::capture::{self, BeaconSample, LiveCapture};
+use triangle_gator::device_profile::{DeviceProfile, DeviceProfiles};
+use triangle_gator::geo::GeoOrigin;
+use triangle_gator::gpsd;
+use triangle_gator::interfaces;
+use triangle_gator::logging;
+use triangle_gator::network_list::{self, NetworkColumn};
+use triangle_gator::network_manager;
+use triangle_gator::sensor::{self, Collector, SensorAgent};
+use triangle_gator::{DistanceUnit, LinkReading, NetworkManager, Point, Session};
+
+const USAGE: &str = "usage: gator-cli <interfaces|scan|beacons|measure|solve|agent|collect|calibrate> [options]
+
+  interfaces                 List wireless interfaces with their driver, MAC and capabilities
+  scan                       List available networks
+  beacons --pcap FILE        List the access points heard in a pcap/pcapng capture with radiotap headers
+          --monitor SECS     ...or heard live for a while by a monitor mode --interface, through tcpdump
+  measure --point x,y[,z]    Sample the current link and append a point to the session
+          --latlon LAT,LON   ...at a latitude/longitude instead (georeferences the session if needed)
+          --gps              ...wherever gpsd says we are
+          --gpsd ADDR        gpsd to ask (default 127.0.0.1:2947)
+          --samples N        Number of readings to average (default 10)
+          --interval MS      Delay between readings (default 200)
+          --pcap FILE        Take the readings from the beacons of --bssid in a capture instead
+          --monitor SECS     ...or from its beacons heard live by a monitor mode --interface
+          --bssid MAC        Access point to measure from beacons
+          --tx-power DBM     TX power for access points that don't advertise theirs (default 20)
+  solve                      Estimate the network location from the session's points
+          --exponent N       Path loss exponent (default: the session's)
+  agent --point x,y[,z]      Stream readings taken at a fixed position to a collector
+        --id NAME            Name of this sensor (default: hostname)
+        --collector ADDR     Collector to report to (default 127.0.0.1:47800)
+        --interval MS        Delay between readings (default 200)
+        --simulate RSSI[,TX] Send fixed readings instead of sampling the link
+  collect                    Receive agent readings and solve them live
+          --port N           Port to listen on (default 47800)
+  calibrate --reference DBM  Match this adapter to a reference device's reading, taken at the same spot
+            --gain DBI       Receive antenna gain of this adapter (default: the saved profile's, or 0)
+            --name NAME      Name to save the profile under (default: the interface's)
+            --samples N      Number of readings to average (default 10)
+            --interval MS    Delay between readings (default 200)
+
+  --interface IF             Wireless interface to scan and measure on (default: the first)
+  --unit m|ft                Unit of --point and of the results (converts an existing session)
+  --origin LAT,LON           Georeference the session, with (0, 0) at this latitude/longitude
+  --session FILE             Session file (default session.json)
+  --json                     Print machine readable output
+  --log FILE                 Also append diagnostics to a file (stderr gets them either way, filtered by RUST_LOG)";
+
+struct Options {
+    session: PathBuf,
+    json: bool,
+    point: Option<Point>,
+    samples: u16,
+    interval: u64,
+    exponent: Option<f32>,
+    id: Option<String>,
+    collector: String,
+    port: u16,
+    simulate: Option<LinkReading>,
+    latlon: Option<(f64, f64)>,
+    gps: bool,
+    gpsd: String,
+    origin: Option<(f64, f64)>,
+    unit: Option<DistanceUnit>,
+    reference: Option<f32>,
+    gain: Option<f32>,
+    name: Option<String>,
+    interface: Option<String>,
+    pcap: Option<PathBuf>,
+    monitor: Option<u64>,
+    bssid: Option<String>,
+    tx_power: f32,
+    log: Option<PathBuf>,
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let Some(command) = args.first() else {
+        eprintln!("{}", USAGE);
+        return ExitCode::FAILURE;
+    };
+
+    let options = match parse_options(&args[1..]) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let logging = logging::init(logging::DEFAULT_FILTER);
+    if let Some(path) = &options.log {
+        if let Err(e) = logging.file.open(path) {
+            eprintln!("error: {}: {}", path.display(), e);
+            return ExitCode::FAILURE;
+        }
+    }
+
+    let result = match command.as_str() {
+        "interfaces" => list_interfaces(&options),
+        "scan" => scan(&options),
+        "beacons" => beacons(&options),
+        "measure" => measure(&options),
+        "solve" => solve(&options),
+        "agent" => agent(&options),
+        "collect" => collect(&options),
+        "calibrate" => calibrate(&options),
+        "help" | "--help" | "-h" => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        _ => Err(format!("unknown command '{}'", command)),
+    };
+
+    if let Err(e) = result {
+        if options.json {
+            println!("{}", json!({ "error": e }));
+        } else {
+            eprintln!("error: {}", e);
+        }
+        return ExitCode::FAILURE;
+    }
+
+    return ExitCode::SUCCESS;
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut options = Options {
+        session: PathBuf::from("session.json"),
+        json: false,
+        point: None,
+        samples: 10,
+        interval: 200,
+        exponent: None,
+        id: None,
+        collector: format!("127.0.0.1:{}", sensor::DEFAULT_PORT),
+        port: sensor::DEFAULT_PORT,
+        simulate: None,
+        latlon: None,
+        gps: false,
+        gpsd: String::from(gpsd::DEFAULT_ADDR),
+        origin: None,
+        unit: None,
+        reference: None,
+        gain: None,
+        name: None,
+        interface: None,
+        pcap: None,
+        monitor: None,
+        bssid: None,
+        tx_power: capture::DEFAULT_TX_POWER,
+        log: None,
+    };
+
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("{} needs a value", arg));
+
+        match arg.as_str() {
+            "--json" => options.json = true,
+            "--session" => options.session = PathBuf::from(value()?),
+            "--point" => options.point = Some(parse_point(value()?)?),
+            "--samples" => options.samples = value()?.parse().map_err(|_| String::from("--samples must be a whole number"))?,
+            "--interval" => options.interval = value()?.parse().map_err(|_| String::from("--interval must be a whole number"))?,
+            "--exponent" => options.exponent = Some(value()?.parse().map_err(|_| String::from("--exponent must be a number"))?),
+            "--id" => options.id = Some(value()?.clone()),
+            "--collector" => options.collector = value()?.clone(),
+            "--port" => options.port = value()?.parse().map_err(|_| String::from("--port must be a port number"))?,
+            "--simulate" => options.simulate = Some(parse_simulated(value()?)?),
+            "--latlon" => options.latlon = Some(parse_latlon(value()?)?),
+            "--origin" => options.origin = Some(parse_latlon(value()?)?),
+            "--gps" => options.gps = true,
+            "--gpsd" => options.gpsd = value()?.clone(),
+            "--unit" => options.unit = Some(DistanceUnit::parse(value()?).ok_or(String::from("--unit must be m or ft"))?),
+            "--reference" => options.reference = Some(value()?.parse().map_err(|_| String::from("--reference must be a number of dBm"))?),
+            "--gain" => options.gain = Some(value()?.parse().map_err(|_| String::from("--gain must be a number of dBi"))?),
+            "--name" => options.name = Some(value()?.clone()),
+            "--interface" => options.interface = Some(value()?.clone()),
+            "--pcap" => options.pcap = Some(PathBuf::from(value()?)),
+            "--monitor" => options.monitor = Some(value()?.parse().map_err(|_| String::from("--monitor must be a whole number of seconds"))?),
+            "--bssid" => options.bssid = Some(value()?.clone()),
+            "--log" => options.log = Some(PathBuf::from(value()?)),
+            "--tx-power" => options.tx_power = value()?.parse().map_err(|_| String::from("--tx-power must be a number of dBm"))?,
+            _ => return Err(format!("unknown option '{}'", arg)),
+        }
+    }
+
+    return Ok(options);
+}
+
+fn parse_point(value: &str) -> Result<Point, String> {
+    let coords: Result<Vec<f32>, _> = value.split(',').map(|coord| coord.trim().parse::<f32>()).collect();
+
+    return match coords.as_deref() {
+        Ok([x, y]) => Ok(Point::new(*x, *y, None)),
+        Ok([x, y, z]) => {
+            let mut point = Point::new(*x, *y, None);
+            point.z = *z;
+            Ok(point)
+        }
+        _ => Err(format!("--point must be x,y or x,y,z, got '{}'", value)),
+    };
+}
+
+fn parse_latlon(value: &str) -> Result<(f64, f64), String> {
+    let coords: Result<Vec<f64>, _> = value.split(',').map(|coord| coord.trim().parse::<f64>()).collect();
+
+    return match coords.as_deref() {
+        Ok([latitude, longitude]) if latitude.abs() <= 90.0 && longitude.abs() <= 180.0 => Ok((*latitude, *longitude)),
+        _ => Err(format!("expected LAT,LON in degrees, got '{}'", value)),
+    };
+}
+
+fn parse_simulated(value: &str) -> Result<LinkReading, String> {
+    let levels: Result<Vec<f32>, _> = value.split(',').map(|level| level.trim().parse::<f32>()).collect();
+
+    return match levels.as_deref() {
+        Ok([signal_level]) => Ok(LinkReading { signal_level: *signal_level, tx_power: 20.0 }),
+        Ok([signal_level, tx_power]) => Ok(LinkReading { signal_level: *signal_level, tx_power: *tx_power }),
+        _ => Err(format!("--simulate must be RSSI or RSSI,TX, got '{}'", value)),
+    };
+}
+
+// An existing session file, or an empty session to start one
+fn load_session(options: &Options) -> Result<Session, String> {
+    let mut session = if options.session.exists() {
+        Session::load(&options.session).map_err(|e| format!("{}: {}", options.session.display(), e))?
+    } else {
+        Session { points: vec![], ..Session::default() }
+    };
+
+    if let Some((latitude, longitude)) = options.origin {
+        session.origin = Some(GeoOrigin::new(latitude, longitude));
+    }
+
+    if let Some(unit) = options.unit {
+        session.set_unit(unit);
+    }
+
+    return Ok(session);
+}
+
+// Where measure was asked to take the reading, in the session's local frame
+fn measure_position(options: &Options, session: &mut Session) -> Result<Point, String> {
+    let latlon = if options.gps {
+        let fix = gpsd::read_fix(&options.gpsd, time::Duration::from_secs(10)).map_err(|e| format!("gpsd {}: {}", options.gpsd, e))?;
+        Some((fix.latitude, fix.longitude))
+    } else {
+        options.latlon
+    };
+
+    let Some((latitude, longitude)) = latlon else {
+        return options.point.clone().ok_or(String::from("measure needs --point x,y, --latlon LAT,LON or --gps"));
+    };
+
+    // The first geodetic point anchors a session that isn't georeferenced yet
+    if session.origin.is_none() {
+        session.origin = Some(GeoOrigin::new(latitude, longitude));
+    }
+
+    return Ok(session.point_at(latitude, longitude).unwrap());
+}
+
+// Network manager on the chosen interface, with that adapter's device profile
+fn network_manager(options: &Options) -> NetworkManager {
+    let mut network_manager = NetworkManager::default();
+    network_manager.set_interface(options.interface.clone());
+
+    return network_manager;
+}
+
+// Beacons from --pcap or --monitor, None if neither was given
+fn beacon_samples(options: &Options) -> Result<Option<Vec<BeaconSample>>, String> {
+    if let Some(path) = &options.pcap {
+        return capture::read_file(path).map(Some).map_err(|e| format!("{}: {}", path.display(), e));
+    }
+
+    let Some(seconds) = options.monitor else {
+        return Ok(None);
+    };
+
+    let interface = options.interface.as_deref().ok_or("--monitor needs a monitor mode --interface")?;
+    let live = LiveCapture::start(interface).map_err(|e| format!("tcpdump: {}", e))?;
+
+    let started = Instant::now();
+    let mut samples = Vec::new();
+
+    while started.elapsed() < time::Duration::from_secs(seconds) {
+        thread::sleep(time::Duration::from_millis(100));
+        samples.extend(live.poll());
+    }
+    samples.extend(live.poll());
+
+    // Live samples come from this machine's adapter, so its profile applies
+    if let Some(device_profile) = network_manager(options).get_device_profile() {
+        for sample in samples.iter_mut() {
+            sample.signal_dbm = device_profile.correct_signal(sample.signal_dbm);
+        }
+    }
+
+    return Ok(Some(samples));
+}
+
+fn beacons(options: &Options) -> Result<(), String> {
+    let samples = beacon_samples(options)?.ok_or("beacons needs --pcap FILE or --monitor SECS")?;
+    let summaries = capture::summarise(&samples);
+
+    if options.json {
+        println!("{}", serde_json::to_string_pretty(&summaries).unwrap());
+    } else {
+        for summary in summaries.iter() {
+            println!(
+                "{}  {:>7.2} dBm  {:>5} frames  {:>5}  {}",
+                summary.bssid,
+                summary.mean_dbm,
+                summary.frames,
+                summary.frequency_mhz.map(|frequency| format!("{}", frequency)).unwrap_or_default(),
+                summary.ssid.as_deref().unwrap_or("<hidden>"),
+            );
+        }
+    }
+
+    return Ok(());
+}
+
+fn list_interfaces(options: &Options) -> Result<(), String> {
+    let interfaces = interfaces::wireless_interfaces();
+
+    if options.json {
+        println!("{}", serde_json::to_string_pretty(&interfaces).unwrap());
+    } else {
+        for interface in interfaces.iter() {
+            println!("{}  {}", interface.describe(), interface.capabilities.join(", "));
+        }
+    }
+
+    return Ok(());
+}
+
+fn scan(options: &Options) -> Result<(), String> {
+    let mut network_manager = network_manager(options);
+    network_manager.scan_networks().map_err(|e| e.to_string())?;
+
+    let networks = network_manager.get_available_networks();
+
+    if options.json {
+        println!("{}", serde_json::to_string_pretty(networks).unwrap());
+    } else {
+        // Strongest first
+        let mut networks: Vec<_> = networks.iter().collect();
+        network_list::sort(&mut networks, NetworkColumn::Signal, false);
+
+        for network in networks {
+            let band = match (network.band(), network.channel) {
+                (Some(band), Some(channel)) => format!("{} ch {}", band, channel),
+                _ => String::from("-"),
+            };
+            let flag = if network_list::suspicions(network).is_empty() { "" } else { "  (suspicious)" };
+
+            println!("{:>3}% {:>4} dBm  {:<9}  {:<14}  {:>2} AP  {}{}", network.measured_power, network_list::percent_to_dbm(network.measured_power), network.kind.to_string(), band, network.bssids.len(), network.ssid, flag);
+        }
+    }
+
+    return Ok(());
+}
+
+fn measure(options: &Options) -> Result<(), String> {
+    let mut session = load_session(options)?;
+    let mut point = measure_position(options, &mut session)?;
+
+    let net_info = match beacon_samples(options)? {
+        Some(samples) => {
+            let bssid = options.bssid.as_deref().ok_or("measuring from beacons needs --bssid MAC")?;
+            let net_info = capture::net_info_for(&samples, bssid, options.tx_power);
+
+            if net_info.measured_power.is_none() {
+                return Err(format!("no beacons from {}", bssid));
+            }
+            net_info
+        }
+        None => {
+            let network_manager = network_manager(options);
+            let net_info = network_manager.ping_network(options.samples.max(1), options.interval);
+
+            if net_info.measured_power.is_none() {
+                return Err(String::from("could not read the signal level of the current link"));
+            }
+            net_info
+        }
+    };
+
+    point.net_info = Some(net_info);
+    session.points.push(point.clone());
+    session.save(&options.session).map_err(|e| format!("{}: {}", options.session.display(), e))?;
+
+    if options.json {
+        println!("{}", json!({ "index": session.points.len() - 1, "point": point }));
+    } else {
+        let net_info = point.net_info.as_ref().unwrap();
+        println!(
+            "Point {} at ({}, {}) {}: RSSI {:.2} dBm, TX Power {:.2} dBm over {} samples",
+            session.points.len(),
+            point.x,
+            point.y,
+            session.unit.suffix(),
+            net_info.measured_power.unwrap(),
+            net_info.tx_power.unwrap(),
+            net_info.samples.len(),
+        );
+    }
+
+    return Ok(());
+}
+
+fn solve(options: &Options) -> Result<(), String> {
+    let mut session = load_session(options)?;
+
+    if let Some(exponent) = options.exponent {
+        session.path_loss_exponent = exponent;
+    }
+
+    let location = session.solve().cloned().ok_or("need at least three tested points that don't lie on a line")?;
+    session.save(&options.session).map_err(|e| format!("{}: {}", options.session.display(), e))?;
+
+    let geodetic = session.location_geodetic();
+
+    if options.json {
+        println!("{}", json!({
+            "ssid": session.ssid,
+            "path_loss_exponent": session.path_loss_exponent,
+            "unit": session.unit,
+            "location": location,
+            "latitude": geodetic.map(|(latitude, _)| latitude),
+            "longitude": geodetic.map(|(_, longitude)| longitude),
+        }));
+    } else {
+        let unit = session.unit.suffix();
+        println!("Estimated WAP Location: ({:.2}, {:.2}) {} ± {:.2} {}", location.x, location.y, unit, location.uncertainty, unit);
+
+        if let Some((latitude, longitude)) = geodetic {
+            println!("                        {:.7}, {:.7}", latitude, longitude);
+        }
+    }
+
+    return Ok(());
+}
+
+fn agent(options: &Options) -> Result<(), String> {
+    let position = options.point.clone().ok_or("agent needs --point x,y")?;
+    let sensor_id = options.id.clone().or(env::var("HOSTNAME").ok()).unwrap_or(String::from("sensor"));
+
+    let mut agent = SensorAgent::new(&sensor_id, position, &options.collector).map_err(|e| format!("{}: {}", options.collector, e))?;
+    agent.rate_ms = options.interval;
+
+    info!("Reporting '{}' to {} every {} ms", sensor_id, agent.collector, agent.rate_ms);
+
+    let network_manager = network_manager(options);
+    let device_profile = network_manager.get_device_profile().cloned();
+    let interface = options.interface.clone();
+
+    let running = AtomicBool::new(true);
+    let simulate = options.simulate;
+
+    return agent.run(&running, || {
+        simulate.or_else(|| {
+            let reading = network_manager::read_link(interface.as_deref())?;
+            Some(device_profile.as_ref().map_or(reading, |device_profile| device_profile.correct(reading)))
+        })
+    }).map_err(|e| e.to_string());
+}
+
+fn collect(options: &Options) -> Result<(), String> {
+    let collector = Collector::bind(("0.0.0.0", options.port), sensor::DEFAULT_MAX_AGE).map_err(|e| format!("port {}: {}", options.port, e))?;
+
+    info!("Collecting on {}", collector.local_addr());
+
+    let unit = options.unit.unwrap_or_default();
+    let mut session = Session { points: vec![], unit, ..Session::default() };
+    if let Some(exponent) = options.exponent {
+        session.path_loss_exponent = exponent;
+    }
+
+    loop {
+        thread::sleep(time::Duration::from_secs(1));
+
+        if let Some(e) = collector.take_last_error() {
+            warn!("{}", e);
+        }
+
+        session.points = collector.snapshot();
+        let sensors = session.points.len();
+
+        match session.solve() {
+            Some(location) if options.json => println!("{}", json!({ "sensors": sensors, "unit": unit, "location": location })),
+            Some(location) => println!("{} sensors: ({:.2}, {:.2}) {} ± {:.2} {}", sensors, location.x, location.y, unit.suffix(), location.uncertainty, unit.suffix()),
+            None if !options.json => println!("{} sensors: waiting for at least three", sensors),
+            None => {}
+        }
+    }
+}
+
+fn calibrate(options: &Options) -> Result<(), String> {
+    let reference = options.reference.ok_or("calibrate needs --reference DBM, the reference device's reading")?;
+    let interface = network_manager(options).current_interface().ok_or("no wireless interface found")?;
+
+    let mut profiles = DeviceProfiles::load().map_err(|e| e.to_string())?;
+    let name = options.name.clone().unwrap_or(interface.name.clone());
+    let mut profile = profiles.find(&interface).unwrap_or(DeviceProfile::new(&name, &interface));
+
+    if let Some(gain) = options.gain {
+        profile.rx_gain_dbi = gain;
+    }
+    if options.name.is_some() {
+        profile.name = name;
+    }
+
+    let readings = network_manager::sample_link(Some(&interface.name), options.samples.max(1), options.interval);
+    let offset = profile.calibrate(&readings, reference).ok_or("could not read the signal level of the current link")?;
+
+    profiles.upsert(profile.clone());
+    profiles.save().map_err(|e| e.to_string())?;
+
+    if options.json {
+        println!("{}", json!({ "interface": interface.name, "profile": profile, "samples": readings.len() }));
+    } else {
+        println!(
+            "{} ({}, {}): offset {:+.2} dB, antenna gain {:.2} dBi over {} samples",
+            profile.name,
+            interface.driver.as_deref().unwrap_or("unknown driver"),
+            interface.mac.as_deref().unwrap_or("unknown MAC"),
+            offset,
+            profile.rx_gain_dbi,
+            readings.len(),
+        );
+    }
+
+    return Ok(());
+}
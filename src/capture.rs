@@ -0,0 +1,741 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+use serde::Serialize;
+
+use crate::models::{LinkReading, NetInfo};
+
+pub const DEFAULT_TX_POWER: f32 = 20.0; // dBm, for access points that don't advertise theirs
+
+const LINKTYPE_IEEE802_11_RADIOTAP: u16 = 127;
+
+const PCAP_MAGIC_MICROS: u32 = 0xA1B2_C3D4;
+const PCAP_MAGIC_NANOS: u32 = 0xA1B2_3C4D;
+const PCAPNG_SECTION_HEADER: u32 = 0x0A0D_0D0A;
+const PCAPNG_BYTE_ORDER_MAGIC: u32 = 0x1A2B_3C4D;
+const PCAPNG_INTERFACE_DESCRIPTION: u32 = 1;
+const PCAPNG_SIMPLE_PACKET: u32 = 3;
+const PCAPNG_ENHANCED_PACKET: u32 = 6;
+
+const MAX_BLOCK_LEN: usize = 16 * 1024 * 1024; // Anything bigger is a corrupt length field, not a packet
+
+// One beacon or probe response, as heard by the capturing radio
+#[derive(Clone, Serialize)]
+pub struct BeaconSample {
+    pub timestamp: f64, // Seconds since the epoch
+    pub bssid: String,
+    pub ssid: Option<String>, // None for hidden networks
+    pub signal_dbm: f32,
+    pub frequency_mhz: Option<u16>,
+    pub tx_power_dbm: Option<f32>, // From the TPC Report element, if the access point sends one
+}
+
+#[derive(Debug)]
+pub enum CaptureError {
+    Io(io::Error),
+    Format(String), // Not a pcap/pcapng file, or a damaged one
+}
+
+impl fmt::Display for CaptureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            CaptureError::Io(e) => write!(f, "{}", e),
+            CaptureError::Format(e) => write!(f, "invalid capture: {}", e),
+        };
+    }
+}
+
+impl std::error::Error for CaptureError {}
+
+impl From<io::Error> for CaptureError {
+    fn from(e: io::Error) -> Self {
+        return CaptureError::Io(e);
+    }
+}
+
+// Captured frame, still wrapped in its link layer
+pub struct Packet {
+    pub linktype: u16,
+    pub timestamp: f64,
+    pub data: Vec<u8>,
+}
+
+enum Format {
+    Pcap { big_endian: bool, linktype: u16, nanos: bool },
+    Pcapng { big_endian: bool, interfaces: Vec<(u16, f64)> }, // Link type and timestamp resolution of each interface
+}
+
+// Reads packets one at a time from a pcap or pcapng stream, so a pipe from tcpdump works as well as a file
+pub struct CaptureReader<R: Read> {
+    reader: R,
+    format: Format,
+}
+
+impl<R: Read> CaptureReader<R> {
+    pub fn new(mut reader: R) -> Result<CaptureReader<R>, CaptureError> {
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+
+        let format = match (u32::from_le_bytes(magic), u32::from_be_bytes(magic)) {
+            (PCAPNG_SECTION_HEADER, _) => {
+                let mut capture = CaptureReader { reader, format: Format::Pcapng { big_endian: false, interfaces: vec![] } };
+                capture.read_section_header()?;
+                return Ok(capture);
+            }
+            (PCAP_MAGIC_MICROS, _) => (false, false),
+            (PCAP_MAGIC_NANOS, _) => (false, true),
+            (_, PCAP_MAGIC_MICROS) => (true, false),
+            (_, PCAP_MAGIC_NANOS) => (true, true),
+            _ => return Err(CaptureError::Format(String::from("unknown file magic"))),
+        };
+
+        let (big_endian, nanos) = format;
+        let mut header = [0u8; 20];
+        reader.read_exact(&mut header)?;
+        let linktype = read_u32(&header[16..20], big_endian) as u16;
+
+        return Ok(CaptureReader { reader, format: Format::Pcap { big_endian, linktype, nanos } });
+    }
+
+    // Next packet of any kind, None at the end of the stream
+    pub fn next_packet(&mut self) -> Result<Option<Packet>, CaptureError> {
+        return match self.format {
+            Format::Pcap { big_endian, linktype, nanos } => self.next_pcap_packet(big_endian, linktype, nanos),
+            Format::Pcapng { .. } => self.next_pcapng_packet(),
+        };
+    }
+
+    // Next beacon or probe response with a signal reading, skipping every other frame
+    pub fn next_beacon(&mut self) -> Result<Option<BeaconSample>, CaptureError> {
+        while let Some(packet) = self.next_packet()? {
+            if packet.linktype != LINKTYPE_IEEE802_11_RADIOTAP {
+                continue;
+            }
+
+            if let Some(sample) = parse_radiotap_beacon(&packet.data, packet.timestamp) {
+                return Ok(Some(sample));
+            }
+        }
+
+        return Ok(None);
+    }
+
+    fn next_pcap_packet(&mut self, big_endian: bool, linktype: u16, nanos: bool) -> Result<Option<Packet>, CaptureError> {
+        let mut header = [0u8; 16];
+        if !read_or_eof(&mut self.reader, &mut header)? {
+            return Ok(None);
+        }
+
+        let seconds = read_u32(&header[0..4], big_endian);
+        let fraction = read_u32(&header[4..8], big_endian);
+        let captured_len = read_u32(&header[8..12], big_endian) as usize;
+
+        if captured_len > MAX_BLOCK_LEN {
+            return Err(CaptureError::Format(format!("packet of {} bytes", captured_len)));
+        }
+
+        let mut data = vec![0u8; captured_len];
+        self.reader.read_exact(&mut data)?;
+
+        let timestamp = f64::from(seconds) + f64::from(fraction) * if nanos { 1e-9 } else { 1e-6 };
+
+        return Ok(Some(Packet { linktype, timestamp, data }));
+    }
+
+    fn next_pcapng_packet(&mut self) -> Result<Option<Packet>, CaptureError> {
+        loop {
+            let mut header = [0u8; 8];
+            if !read_or_eof(&mut self.reader, &mut header)? {
+                return Ok(None);
+            }
+
+            if u32::from_le_bytes([header[0], header[1], header[2], header[3]]) == PCAPNG_SECTION_HEADER {
+                // A new section, possibly of the other byte order
+                self.read_section_header_after(&header)?;
+                continue;
+            }
+
+            let Format::Pcapng { big_endian, .. } = self.format else {
+                unreachable!();
+            };
+
+            let block_type = read_u32(&header[0..4], big_endian);
+            let block_len = read_u32(&header[4..8], big_endian) as usize;
+
+            if !(12..=MAX_BLOCK_LEN).contains(&block_len) || !block_len.is_multiple_of(4) {
+                return Err(CaptureError::Format(format!("block of {} bytes", block_len)));
+            }
+
+            let mut body = vec![0u8; block_len - 8];
+            self.reader.read_exact(&mut body)?;
+            body.truncate(block_len - 12); // Trailing copy of the length
+
+            let Format::Pcapng { interfaces, .. } = &mut self.format else {
+                unreachable!();
+            };
+
+            match block_type {
+                PCAPNG_INTERFACE_DESCRIPTION if body.len() >= 8 => {
+                    let linktype = read_u16(&body[0..2], big_endian);
+                    interfaces.push((linktype, timestamp_resolution(&body[8..], big_endian)));
+                }
+                PCAPNG_ENHANCED_PACKET if body.len() >= 20 => {
+                    let interface = read_u32(&body[0..4], big_endian) as usize;
+                    let ticks = (u64::from(read_u32(&body[4..8], big_endian)) << 32) | u64::from(read_u32(&body[8..12], big_endian));
+                    let captured_len = (read_u32(&body[12..16], big_endian) as usize).min(body.len() - 20);
+
+                    let Some((linktype, resolution)) = interfaces.get(interface) else {
+                        return Err(CaptureError::Format(format!("packet on undeclared interface {}", interface)));
+                    };
+
+                    return Ok(Some(Packet { linktype: *linktype, timestamp: ticks as f64 * resolution, data: body[20..20 + captured_len].to_vec() }));
+                }
+                PCAPNG_SIMPLE_PACKET if body.len() >= 4 => {
+                    let original_len = read_u32(&body[0..4], big_endian) as usize;
+                    let captured_len = original_len.min(body.len() - 4);
+
+                    let Some((linktype, _)) = interfaces.first() else {
+                        return Err(CaptureError::Format(String::from("packet before any interface")));
+                    };
+
+                    return Ok(Some(Packet { linktype: *linktype, timestamp: 0.0, data: body[4..4 + captured_len].to_vec() }));
+                }
+                _ => {} // Statistics, name resolution and the like
+            }
+        }
+    }
+
+    fn read_section_header(&mut self) -> Result<(), CaptureError> {
+        let mut rest = [0u8; 4];
+        self.reader.read_exact(&mut rest)?;
+
+        let mut header = [0u8; 8];
+        header[0..4].copy_from_slice(&PCAPNG_SECTION_HEADER.to_le_bytes());
+        header[4..8].copy_from_slice(&rest);
+
+        return self.read_section_header_after(&header);
+    }
+
+    // Reads the rest of a section header block, given its type and length
+    fn read_section_header_after(&mut self, header: &[u8; 8]) -> Result<(), CaptureError> {
+        let mut byte_order = [0u8; 4];
+        self.reader.read_exact(&mut byte_order)?;
+
+        let big_endian = match (u32::from_le_bytes(byte_order), u32::from_be_bytes(byte_order)) {
+            (PCAPNG_BYTE_ORDER_MAGIC, _) => false,
+            (_, PCAPNG_BYTE_ORDER_MAGIC) => true,
+            _ => return Err(CaptureError::Format(String::from("bad pcapng byte order magic"))),
+        };
+
+        let block_len = read_u32(&header[4..8], big_endian) as usize;
+        if !(28..=MAX_BLOCK_LEN).contains(&block_len) {
+            return Err(CaptureError::Format(format!("section header of {} bytes", block_len)));
+        }
+
+        let mut rest = vec![0u8; block_len - 12];
+        self.reader.read_exact(&mut rest)?;
+
+        // Interfaces are numbered per section
+        self.format = Format::Pcapng { big_endian, interfaces: vec![] };
+
+        return Ok(());
+    }
+}
+
+// Every beacon in a pcap or pcapng file
+pub fn read_file(path: &Path) -> Result<Vec<BeaconSample>, CaptureError> {
+    let mut reader = CaptureReader::new(BufReader::new(File::open(path)?))?;
+    let mut samples = Vec::new();
+
+    while let Some(sample) = reader.next_beacon()? {
+        samples.push(sample);
+    }
+
+    return Ok(samples);
+}
+
+// Beacons heard live by a monitor mode interface, through tcpdump
+pub struct LiveCapture {
+    child: Child,
+    receiver: Receiver<BeaconSample>,
+}
+
+impl LiveCapture {
+    // The interface has to be in monitor mode already, e.g. `iw dev wlan1 set type monitor`
+    pub fn start(interface: &str) -> io::Result<LiveCapture> {
+        let mut child = Command::new("tcpdump")
+            .args(["-i", interface, "-y", "IEEE802_11_RADIO", "-U", "-w", "-", "type", "mgt"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+
+        let stdout = child.stdout.take().unwrap();
+        let (sender, receiver) = mpsc::channel();
+
+        thread::spawn(move || {
+            let Ok(mut reader) = CaptureReader::new(BufReader::new(stdout)) else {
+                return;
+            };
+
+            while let Ok(Some(sample)) = reader.next_beacon() {
+                if sender.send(sample).is_err() {
+                    break;
+                }
+            }
+        });
+
+        return Ok(LiveCapture { child, receiver });
+    }
+
+    // Beacons heard since the last poll
+    pub fn poll(&self) -> Vec<BeaconSample> {
+        return self.receiver.try_iter().collect();
+    }
+
+    pub fn stop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+impl Drop for LiveCapture {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+// Every access point heard, loudest first
+#[derive(Clone, Serialize)]
+pub struct BssidSummary {
+    pub bssid: String,
+    pub ssid: Option<String>,
+    pub frequency_mhz: Option<u16>,
+    pub frames: usize,
+    pub mean_dbm: f32,
+}
+
+pub fn summarise(samples: &[BeaconSample]) -> Vec<BssidSummary> {
+    let mut summaries: Vec<BssidSummary> = Vec::new();
+
+    for sample in samples {
+        match summaries.iter_mut().find(|summary| summary.bssid == sample.bssid) {
+            Some(summary) => {
+                summary.mean_dbm += (sample.signal_dbm - summary.mean_dbm) / (summary.frames + 1) as f32;
+                summary.frames += 1;
+                summary.ssid = summary.ssid.take().or(sample.ssid.clone());
+            }
+            None => summaries.push(BssidSummary {
+                bssid: sample.bssid.clone(),
+                ssid: sample.ssid.clone(),
+                frequency_mhz: sample.frequency_mhz,
+                frames: 1,
+                mean_dbm: sample.signal_dbm,
+            }),
+        }
+    }
+
+    summaries.sort_by(|a, b| b.mean_dbm.total_cmp(&a.mean_dbm));
+
+    return summaries;
+}
+
+// Folds one BSSID's beacons into a measurement, just like readings of an associated link
+pub fn net_info_for(samples: &[BeaconSample], bssid: &str, default_tx_power: f32) -> NetInfo {
+    let readings: Vec<LinkReading> = samples.iter()
+        .filter(|sample| sample.bssid.eq_ignore_ascii_case(bssid))
+        .map(|sample| LinkReading { signal_level: sample.signal_dbm, tx_power: sample.tx_power_dbm.unwrap_or(default_tx_power) })
+        .collect();
+
+    return NetInfo::from_readings(&readings);
+}
+
+// Radiotap header, then an 802.11 beacon or probe response
+fn parse_radiotap_beacon(data: &[u8], timestamp: f64) -> Option<BeaconSample> {
+    let radiotap = parse_radiotap(data)?;
+    let header_len = usize::from(u16::from_le_bytes([data[2], data[3]]));
+    let mut frame = data.get(header_len..)?;
+
+    if radiotap.has_fcs {
+        frame = frame.get(..frame.len().checked_sub(4)?)?;
+    }
+
+    let frame_control = *frame.first()?;
+    let frame_type = (frame_control >> 2) & 0b11;
+    let subtype = frame_control >> 4;
+
+    // Management frames only, beacon (8) or probe response (5)
+    if frame_type != 0 || (subtype != 8 && subtype != 5) {
+        return None;
+    }
+
+    let bssid = frame.get(16..22)?.iter().map(|byte| format!("{:02x}", byte)).collect::<Vec<String>>().join(":");
+
+    // Fixed fields (timestamp, interval, capabilities) come before the tagged elements
+    let mut elements = frame.get(36..)?;
+    let mut ssid = None;
+    let mut tx_power_dbm = None;
+
+    while let [id, len, rest @ ..] = elements {
+        let Some(value) = rest.get(..usize::from(*len)) else {
+            break; // Truncated by the snap length, or the FCS got in the way
+        };
+
+        match id {
+            0 if !value.is_empty() && value.iter().any(|byte| *byte != 0) => ssid = Some(String::from_utf8_lossy(value).into_owned()),
+            35 if value.len() >= 2 => tx_power_dbm = Some(f32::from(value[0] as i8)), // TPC Report
+            _ => {}
+        }
+
+        elements = &rest[usize::from(*len)..];
+    }
+
+    return Some(BeaconSample {
+        timestamp,
+        bssid,
+        ssid,
+        signal_dbm: radiotap.signal_dbm,
+        frequency_mhz: radiotap.frequency_mhz,
+        tx_power_dbm,
+    });
+}
+
+struct Radiotap {
+    signal_dbm: f32,
+    frequency_mhz: Option<u16>,
+    has_fcs: bool, // The frame ends in its 4 byte checksum
+}
+
+// Antenna signal and channel frequency out of a radiotap header. Fields sit in bit order, each
+// aligned to its own size, so every field before the signal has to be stepped over.
+fn parse_radiotap(data: &[u8]) -> Option<Radiotap> {
+    if data.len() < 8 || data[0] != 0 {
+        return None;
+    }
+
+    let header_len = usize::from(u16::from_le_bytes([data[2], data[3]]));
+    let header = data.get(..header_len)?;
+
+    let present = u32::from_le_bytes(header.get(4..8)?.try_into().ok()?);
+
+    // Skip any extended presence words
+    let mut offset = 8;
+    let mut word = present;
+    while word & (1 << 31) != 0 {
+        word = u32::from_le_bytes(header.get(offset..offset + 4)?.try_into().ok()?);
+        offset += 4;
+    }
+
+    // (alignment, size) of fields 0 to 5: TSFT, flags, rate, channel, FHSS, antenna signal
+    const FIELDS: [(usize, usize); 6] = [(8, 8), (1, 1), (1, 1), (2, 4), (1, 2), (1, 1)];
+
+    let mut frequency_mhz = None;
+    let mut has_fcs = false;
+
+    for (bit, (align, size)) in FIELDS.iter().enumerate() {
+        if present & (1 << bit) == 0 {
+            continue;
+        }
+
+        offset = offset.next_multiple_of(*align);
+        let field = header.get(offset..offset + size)?;
+
+        match bit {
+            1 => has_fcs = field[0] & 0x10 != 0,
+            3 => frequency_mhz = Some(u16::from_le_bytes([field[0], field[1]])),
+            5 => return Some(Radiotap { signal_dbm: f32::from(field[0] as i8), frequency_mhz, has_fcs }),
+            _ => {}
+        }
+
+        offset += size;
+    }
+
+    return None; // No antenna signal
+}
+
+// Resolution of an interface's timestamps, from its if_tsresol option. Microseconds if absent.
+fn timestamp_resolution(mut options: &[u8], big_endian: bool) -> f64 {
+    while options.len() >= 4 {
+        let code = read_u16(&options[0..2], big_endian);
+        let len = usize::from(read_u16(&options[2..4], big_endian));
+        let Some(value) = options.get(4..4 + len) else {
+            break;
+        };
+
+        if code == 9 && len >= 1 {
+            let exponent = i32::from(value[0] & 0x7F);
+            return if value[0] & 0x80 == 0 { 10f64.powi(-exponent) } else { 2f64.powi(-exponent) };
+        }
+        if code == 0 {
+            break;
+        }
+
+        options = options.get(4 + len.next_multiple_of(4)..).unwrap_or(&[]);
+    }
+
+    return 1e-6;
+}
+
+// Fills buf, or returns false if the stream ended cleanly before the first byte
+fn read_or_eof(reader: &mut impl Read, buf: &mut [u8]) -> Result<bool, CaptureError> {
+    let mut filled = 0;
+
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) if filled == 0 => return Ok(false),
+            Ok(0) => return Err(CaptureError::Format(String::from("truncated record"))),
+            Ok(read) => filled += read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    return Ok(true);
+}
+
+fn read_u16(bytes: &[u8], big_endian: bool) -> u16 {
+    let bytes = [bytes[0], bytes[1]];
+
+    return if big_endian { u16::from_be_bytes(bytes) } else { u16::from_le_bytes(bytes) };
+}
+
+fn read_u32(bytes: &[u8], big_endian: bool) -> u32 {
+    let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+
+    return if big_endian { u32::from_be_bytes(bytes) } else { u32::from_le_bytes(bytes) };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BSSID: [u8; 6] = [0x00, 0x11, 0x22, 0xaa, 0xbb, 0xcc];
+
+    // Radiotap header with flags, channel and antenna signal. Chained adds a second (empty) presence word,
+    // which shifts every field along by four bytes.
+    fn radiotap(signal_dbm: i8, frequency_mhz: u16, fcs: bool, chained: bool) -> Vec<u8> {
+        let present: u32 = (1 << 1) | (1 << 3) | (1 << 5) | if chained { 1 << 31 } else { 0 };
+
+        let mut header = vec![0, 0, 0, 0];
+        header.extend(present.to_le_bytes());
+        if chained {
+            header.extend(0u32.to_le_bytes());
+        }
+
+        header.push(if fcs { 0x10 } else { 0 }); // Flags
+        header.push(0); // Pad the channel to 2 bytes
+        header.extend(frequency_mhz.to_le_bytes());
+        header.extend(0x00A0u16.to_le_bytes()); // Channel flags
+        header.push(signal_dbm as u8);
+
+        let len = header.len() as u16;
+        header[2..4].copy_from_slice(&len.to_le_bytes());
+
+        return header;
+    }
+
+    // Beacon from BSSID, with an SSID element and optionally a TPC Report
+    fn beacon(ssid: &[u8], tx_power_dbm: Option<i8>) -> Vec<u8> {
+        let mut frame = vec![0x80, 0x00, 0, 0]; // Beacon, duration
+        frame.extend([0xff; 6]); // Destination
+        frame.extend(BSSID); // Source
+        frame.extend(BSSID); // BSSID
+        frame.extend([0, 0]); // Sequence
+        frame.extend([0; 12]); // Timestamp, interval, capabilities
+
+        frame.extend([0, ssid.len() as u8]);
+        frame.extend(ssid);
+
+        if let Some(tx_power_dbm) = tx_power_dbm {
+            frame.extend([35, 2, tx_power_dbm as u8, 0]);
+        }
+
+        return frame;
+    }
+
+    fn pcap_le(packets: &[(u32, u32, Vec<u8>)]) -> Vec<u8> {
+        let mut file = Vec::new();
+        file.extend(PCAP_MAGIC_MICROS.to_le_bytes());
+        file.extend(2u16.to_le_bytes());
+        file.extend(4u16.to_le_bytes());
+        file.extend([0; 8]); // Time zone and accuracy
+        file.extend(65535u32.to_le_bytes());
+        file.extend(u32::from(LINKTYPE_IEEE802_11_RADIOTAP).to_le_bytes());
+
+        for (seconds, micros, data) in packets {
+            file.extend(seconds.to_le_bytes());
+            file.extend(micros.to_le_bytes());
+            file.extend((data.len() as u32).to_le_bytes());
+            file.extend((data.len() as u32).to_le_bytes());
+            file.extend(data);
+        }
+
+        return file;
+    }
+
+    fn pcapng_block_be(block_type: u32, body: &[u8]) -> Vec<u8> {
+        let padded = body.len().next_multiple_of(4);
+        let len = (12 + padded) as u32;
+
+        let mut block = Vec::new();
+        block.extend(block_type.to_be_bytes());
+        block.extend(len.to_be_bytes());
+        block.extend(body);
+        block.extend(vec![0; padded - body.len()]);
+        block.extend(len.to_be_bytes());
+
+        return block;
+    }
+
+    // Big endian section with one radiotap interface in nanoseconds
+    fn pcapng_be(packets: &[(u64, Vec<u8>)]) -> Vec<u8> {
+        let mut section = Vec::new();
+        section.extend(PCAPNG_BYTE_ORDER_MAGIC.to_be_bytes());
+        section.extend(1u16.to_be_bytes());
+        section.extend(0u16.to_be_bytes());
+        section.extend([0xff; 8]); // Section length unknown
+        let mut file = pcapng_block_be(PCAPNG_SECTION_HEADER, &section);
+
+        let mut interface = Vec::new();
+        interface.extend(LINKTYPE_IEEE802_11_RADIOTAP.to_be_bytes());
+        interface.extend([0, 0]);
+        interface.extend(65535u32.to_be_bytes());
+        interface.extend(9u16.to_be_bytes()); // if_tsresol
+        interface.extend(1u16.to_be_bytes());
+        interface.extend([9, 0, 0, 0]); // 10^-9, padded
+        interface.extend([0, 0, 0, 0]); // opt_endofopt
+        file.extend(pcapng_block_be(PCAPNG_INTERFACE_DESCRIPTION, &interface));
+
+        for (nanos, data) in packets {
+            let mut packet = Vec::new();
+            packet.extend(0u32.to_be_bytes());
+            packet.extend(((nanos >> 32) as u32).to_be_bytes());
+            packet.extend((*nanos as u32).to_be_bytes());
+            packet.extend((data.len() as u32).to_be_bytes());
+            packet.extend((data.len() as u32).to_be_bytes());
+            packet.extend(data);
+            file.extend(pcapng_block_be(PCAPNG_ENHANCED_PACKET, &packet));
+        }
+
+        return file;
+    }
+
+    fn read_all(bytes: &[u8]) -> Result<Vec<BeaconSample>, CaptureError> {
+        let mut reader = CaptureReader::new(bytes)?;
+        let mut samples = Vec::new();
+
+        while let Some(sample) = reader.next_beacon()? {
+            samples.push(sample);
+        }
+
+        return Ok(samples);
+    }
+
+    #[test]
+    fn reads_little_endian_pcap() {
+        let packet = [radiotap(-42, 2437, false, false), beacon(b"Lab", Some(17))].concat();
+        let samples = read_all(&pcap_le(&[(1_700_000_000, 250_000, packet)])).unwrap();
+
+        assert_eq!(samples.len(), 1);
+        let sample = &samples[0];
+        assert_eq!(sample.bssid, "00:11:22:aa:bb:cc");
+        assert_eq!(sample.ssid.as_deref(), Some("Lab"));
+        assert_eq!(sample.signal_dbm, -42.0);
+        assert_eq!(sample.frequency_mhz, Some(2437));
+        assert_eq!(sample.tx_power_dbm, Some(17.0));
+        assert!((sample.timestamp - 1_700_000_000.25).abs() < 1e-6);
+    }
+
+    #[test]
+    fn reads_big_endian_pcapng() {
+        let packet = [radiotap(-67, 5180, false, false), beacon(b"Office", None)].concat();
+        let samples = read_all(&pcapng_be(&[(1_700_000_000_500_000_000, packet)])).unwrap();
+
+        assert_eq!(samples.len(), 1);
+        let sample = &samples[0];
+        assert_eq!(sample.bssid, "00:11:22:aa:bb:cc");
+        assert_eq!(sample.ssid.as_deref(), Some("Office"));
+        assert_eq!(sample.signal_dbm, -67.0);
+        assert_eq!(sample.frequency_mhz, Some(5180));
+        assert_eq!(sample.tx_power_dbm, None);
+        assert!((sample.timestamp - 1_700_000_000.5).abs() < 1e-3);
+    }
+
+    #[test]
+    fn steps_over_chained_presence_words_and_strips_the_fcs() {
+        // The FCS looks like a TPC Report, so it would be read as one if it weren't stripped
+        let fcs = [35, 2, 5, 0];
+        let packet = [radiotap(-55, 2462, true, true), beacon(b"Lab", None), fcs.to_vec()].concat();
+        let samples = read_all(&pcap_le(&[(0, 0, packet)])).unwrap();
+
+        assert_eq!(samples.len(), 1);
+        assert_eq!(samples[0].signal_dbm, -55.0);
+        assert_eq!(samples[0].frequency_mhz, Some(2462));
+        assert_eq!(samples[0].ssid.as_deref(), Some("Lab"));
+        assert_eq!(samples[0].tx_power_dbm, None);
+    }
+
+    #[test]
+    fn hidden_ssids_have_none() {
+        let blank = [radiotap(-50, 2412, false, false), beacon(&[0; 5], None)].concat();
+        let empty = [radiotap(-50, 2412, false, false), beacon(&[], None)].concat();
+        let samples = read_all(&pcap_le(&[(0, 0, blank), (1, 0, empty)])).unwrap();
+
+        assert_eq!(samples.len(), 2);
+        assert!(samples.iter().all(|sample| sample.ssid.is_none()));
+    }
+
+    #[test]
+    fn truncated_records_are_errors() {
+        let packet = [radiotap(-42, 2437, false, false), beacon(b"Lab", None)].concat();
+        let record_len = 16 + packet.len();
+        let file = pcap_le(&[(0, 0, packet.clone()), (1, 0, packet)]);
+
+        // Cut into the second record's data, then into its header
+        for cut in [file.len() - 10, file.len() - record_len + 6] {
+            let mut reader = CaptureReader::new(&file[..cut]).unwrap();
+
+            assert!(reader.next_beacon().unwrap().is_some());
+            assert!(reader.next_beacon().is_err());
+        }
+    }
+
+    #[test]
+    fn summarises_and_measures_beacons() {
+        let sample = |bssid: &str, ssid: Option<&str>, signal_dbm: f32, tx_power_dbm: Option<f32>| BeaconSample {
+            timestamp: 0.0,
+            bssid: String::from(bssid),
+            ssid: ssid.map(String::from),
+            signal_dbm,
+            frequency_mhz: Some(2437),
+            tx_power_dbm,
+        };
+
+        let packet = [radiotap(-40, 2437, false, false), beacon(b"Lab", Some(17))].concat();
+        let mut samples = read_all(&pcap_le(&[(0, 0, packet)])).unwrap();
+        samples.push(sample("00:11:22:aa:bb:cc", None, -50.0, Some(17.0)));
+        samples.push(sample("66:77:88:99:aa:bb", None, -80.0, None));
+
+        let summaries = summarise(&samples);
+        assert_eq!(summaries.len(), 2);
+        assert_eq!(summaries[0].bssid, "00:11:22:aa:bb:cc");
+        assert_eq!(summaries[0].ssid.as_deref(), Some("Lab"));
+        assert_eq!(summaries[0].frames, 2);
+        assert!((summaries[0].mean_dbm + 45.0).abs() < 1e-4);
+        assert_eq!(summaries[1].frames, 1);
+
+        let net_info = net_info_for(&samples, "00:11:22:AA:BB:CC", DEFAULT_TX_POWER);
+        assert_eq!(net_info.measured_power, Some(-45.0));
+        assert_eq!(net_info.tx_power, Some(17.0));
+        assert_eq!(net_info.samples, vec![-40.0, -50.0]);
+
+        let net_info = net_info_for(&samples, "66:77:88:99:aa:bb", DEFAULT_TX_POWER);
+        assert_eq!(net_info.tx_power, Some(DEFAULT_TX_POWER));
+
+        assert_eq!(net_info_for(&samples, "de:ad:be:ef:00:00", DEFAULT_TX_POWER).measured_power, None);
+    }
+}
@@ -0,0 +1,247 @@
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::interfaces::WirelessInterface;
+use crate::models::LinkReading;
+
+const PROFILES_FILE: &str = "triangle-gator/profiles.json";
+
+// How one adapter's readings differ from a true isotropic dBm. Matched by MAC first, so two
+// laptops sharing a driver can still be told apart, then by driver.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DeviceProfile {
+    pub name: String,
+    pub driver: Option<String>,
+    pub mac: Option<String>,
+    pub rssi_offset_db: f32, // Added to every reading
+    pub rx_gain_dbi: f32, // Receive antenna gain, taken back out of every reading
+}
+
+impl DeviceProfile {
+    pub fn new(name: &str, interface: &WirelessInterface) -> DeviceProfile {
+        return DeviceProfile {
+            name: String::from(name),
+            driver: interface.driver.clone(),
+            mac: interface.mac.clone(),
+            rssi_offset_db: 0.0,
+            rx_gain_dbi: 0.0,
+        };
+    }
+
+    pub fn correct(&self, reading: LinkReading) -> LinkReading {
+        return LinkReading { signal_level: self.correct_signal(reading.signal_level), ..reading };
+    }
+
+    pub fn correct_signal(&self, signal_dbm: f32) -> f32 {
+        return signal_dbm + self.rssi_offset_db - self.rx_gain_dbi;
+    }
+
+    // Sets the offset so this device's readings match a reference device's, both taken at the same
+    // spot from the same network. The readings are raw, before any profile was applied.
+    pub fn calibrate(&mut self, readings: &[LinkReading], reference_rssi: f32) -> Option<f32> {
+        if readings.is_empty() {
+            return None;
+        }
+
+        let mean = readings.iter().map(|reading| reading.signal_level).sum::<f32>() / readings.len() as f32;
+        self.rssi_offset_db = reference_rssi - (mean - self.rx_gain_dbi);
+
+        return Some(self.rssi_offset_db);
+    }
+
+    fn matches_mac(&self, interface: &WirelessInterface) -> bool {
+        return self.mac.is_some() && self.mac == interface.mac;
+    }
+
+    fn matches_driver(&self, interface: &WirelessInterface) -> bool {
+        return self.mac.is_none() && self.driver.is_some() && self.driver == interface.driver;
+    }
+}
+
+#[derive(Debug)]
+pub enum ProfileError {
+    NoConfigDir, // Neither XDG_CONFIG_HOME nor HOME is set
+    Io(io::Error),
+    Json(serde_json::Error),
+}
+
+impl fmt::Display for ProfileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            ProfileError::NoConfigDir => write!(f, "no config directory, set XDG_CONFIG_HOME or HOME"),
+            ProfileError::Io(e) => write!(f, "{}", e),
+            ProfileError::Json(e) => write!(f, "invalid profiles file: {}", e),
+        };
+    }
+}
+
+impl std::error::Error for ProfileError {}
+
+impl From<io::Error> for ProfileError {
+    fn from(e: io::Error) -> Self {
+        return ProfileError::Io(e);
+    }
+}
+
+impl From<serde_json::Error> for ProfileError {
+    fn from(e: serde_json::Error) -> Self {
+        return ProfileError::Json(e);
+    }
+}
+
+// Every profile the team has calibrated, kept in $XDG_CONFIG_HOME/triangle-gator/profiles.json
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct DeviceProfiles {
+    pub profiles: Vec<DeviceProfile>,
+}
+
+impl DeviceProfiles {
+    pub fn path() -> Option<PathBuf> {
+        let config_dir = env::var_os("XDG_CONFIG_HOME").map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+
+        return Some(config_dir.join(PROFILES_FILE));
+    }
+
+    // No profiles file yet is the same as an empty one
+    pub fn load() -> Result<DeviceProfiles, ProfileError> {
+        let path = DeviceProfiles::path().ok_or(ProfileError::NoConfigDir)?;
+
+        return match fs::read_to_string(path) {
+            Ok(contents) => Ok(serde_json::from_str(&contents)?),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(DeviceProfiles::default()),
+            Err(e) => Err(e.into()),
+        };
+    }
+
+    pub fn save(&self) -> Result<(), ProfileError> {
+        let path = DeviceProfiles::path().ok_or(ProfileError::NoConfigDir)?;
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+
+        return Ok(());
+    }
+
+    // The adapter's own profile, or else a copy of one for its driver. The copy takes the adapter's
+    // MAC, so calibrating and saving it adds a profile rather than changing every laptop with that driver.
+    pub fn find(&self, interface: &WirelessInterface) -> Option<DeviceProfile> {
+        if let Some(profile) = self.profiles.iter().find(|profile| profile.matches_mac(interface)) {
+            return Some(profile.clone());
+        }
+
+        let mut profile = self.profiles.iter().find(|profile| profile.matches_driver(interface))?.clone();
+        if interface.mac.is_some() {
+            profile.mac = interface.mac.clone();
+        }
+
+        return Some(profile);
+    }
+
+    // Replaces the profile for the same MAC, or the same driver if it has no MAC, or adds it
+    pub fn upsert(&mut self, profile: DeviceProfile) {
+        let existing = self.profiles.iter_mut().find(|existing| match profile.mac {
+            Some(_) => existing.mac == profile.mac,
+            None => existing.mac.is_none() && existing.driver == profile.driver,
+        });
+
+        match existing {
+            Some(existing) => *existing = profile,
+            None => self.profiles.push(profile),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn interface(driver: &str, mac: Option<&str>) -> WirelessInterface {
+        return WirelessInterface { name: String::from("wlan0"), driver: Some(String::from(driver)), mac: mac.map(String::from), phy: None, capabilities: Vec::new() };
+    }
+
+    fn profile(name: &str, driver: &str, mac: Option<&str>, rssi_offset_db: f32) -> DeviceProfile {
+        return DeviceProfile { rssi_offset_db, ..DeviceProfile::new(name, &interface(driver, mac)) };
+    }
+
+    fn profiles() -> DeviceProfiles {
+        return DeviceProfiles { profiles: vec![
+            profile("iwlwifi", "iwlwifi", None, 2.0),
+            profile("Laptop A", "iwlwifi", Some("aa:aa:aa:aa:aa:aa"), 4.0),
+        ] };
+    }
+
+    #[test]
+    fn finds_by_mac_then_driver() {
+        let profiles = profiles();
+
+        let own = profiles.find(&interface("iwlwifi", Some("aa:aa:aa:aa:aa:aa"))).unwrap();
+        assert_eq!(own, profiles.profiles[1]);
+
+        // Another laptop with the same driver starts from the driver's profile, as itself
+        let other = profiles.find(&interface("iwlwifi", Some("bb:bb:bb:bb:bb:bb"))).unwrap();
+        assert_eq!(other.rssi_offset_db, 2.0);
+        assert_eq!(other.mac.as_deref(), Some("bb:bb:bb:bb:bb:bb"));
+
+        let no_mac = profiles.find(&interface("iwlwifi", None)).unwrap();
+        assert_eq!(no_mac, profiles.profiles[0]);
+
+        assert!(profiles.find(&interface("ath9k", Some("cc:cc:cc:cc:cc:cc"))).is_none());
+    }
+
+    #[test]
+    fn upserts_by_mac_then_driver() {
+        let mut profiles = profiles();
+
+        // Laptop B calibrating and saving leaves laptop A and the driver's profile alone
+        let mut laptop_b = profiles.find(&interface("iwlwifi", Some("bb:bb:bb:bb:bb:bb"))).unwrap();
+        laptop_b.rssi_offset_db = 6.0;
+        profiles.upsert(laptop_b.clone());
+        assert_eq!(profiles.profiles.len(), 3);
+        assert_eq!(profiles.profiles[0].rssi_offset_db, 2.0);
+        assert_eq!(profiles.profiles[1].rssi_offset_db, 4.0);
+        assert_eq!(profiles.profiles[2], laptop_b);
+
+        profiles.upsert(profile("Laptop A", "iwlwifi", Some("aa:aa:aa:aa:aa:aa"), 5.0));
+        assert_eq!(profiles.profiles.len(), 3);
+        assert_eq!(profiles.profiles[1].rssi_offset_db, 5.0);
+
+        profiles.upsert(profile("iwlwifi", "iwlwifi", None, 3.0));
+        assert_eq!(profiles.profiles.len(), 3);
+        assert_eq!(profiles.profiles[0].rssi_offset_db, 3.0);
+
+        profiles.upsert(profile("ath9k", "ath9k", None, 1.0));
+        assert_eq!(profiles.profiles.len(), 4);
+    }
+
+    #[test]
+    fn calibrates_against_a_reference() {
+        let mut profile = profile("Laptop A", "iwlwifi", None, 0.0);
+        profile.rx_gain_dbi = 2.0;
+        let readings = [LinkReading { signal_level: -62.0, tx_power: -30.0 }, LinkReading { signal_level: -66.0, tx_power: -30.0 }];
+
+        // Raw mean -64, -66 once the gain is taken out, so 6 dB short of the reference
+        assert_eq!(profile.calibrate(&readings, -60.0), Some(6.0));
+        assert_eq!(profile.correct_signal(-64.0), -60.0);
+
+        assert_eq!(profile.calibrate(&[], -60.0), None);
+        assert_eq!(profile.rssi_offset_db, 6.0);
+    }
+
+    #[test]
+    fn corrects_only_the_signal() {
+        let mut profile = profile("Laptop A", "iwlwifi", None, 3.0);
+        profile.rx_gain_dbi = 5.0;
+
+        let corrected = profile.correct(LinkReading { signal_level: -70.0, tx_power: -30.0 });
+        assert_eq!(corrected.signal_level, -72.0);
+        assert_eq!(corrected.tx_power, -30.0);
+    }
+}
@@ -0,0 +1,303 @@
+use std::fmt;
+use std::io;
+
+use serde_json::{json, Value};
+
+use crate::session::Session;
+use crate::models::NetInfo;
+
+const UNCERTAINTY_SEGMENTS: usize = 36; // Vertices used to draw the uncertainty circle
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    Csv,
+    GeoJson,
+    Kml,
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        return match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::GeoJson => "geojson",
+            ExportFormat::Kml => "kml",
+        };
+    }
+
+    pub fn needs_origin(&self) -> bool {
+        return *self != ExportFormat::Csv;
+    }
+}
+
+#[derive(Debug)]
+pub enum ExportError {
+    NotGeoreferenced, // GeoJSON and KML need real world coordinates
+    Io(io::Error),
+}
+
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            ExportError::NotGeoreferenced => write!(f, "session has no geodetic origin"),
+            ExportError::Io(e) => write!(f, "{}", e),
+        };
+    }
+}
+
+impl std::error::Error for ExportError {}
+
+impl From<io::Error> for ExportError {
+    fn from(e: io::Error) -> Self {
+        return ExportError::Io(e);
+    }
+}
+
+pub fn to_csv(session: &Session) -> String {
+    let calc = session.calculator();
+    let ssid = session.ssid.clone().unwrap_or_default();
+
+    let mut csv = String::from("kind,index,ssid,x,y,z,rssi_mean,rssi_std_dev,rssi_min,rssi_max,samples,tx_power,distance,uncertainty,unit\n");
+    let unit = session.unit.suffix();
+
+    for (index, point) in session.points.iter().enumerate() {
+        let net_info = point.net_info.as_ref();
+
+        csv.push_str(&format!(
+            "point,{},{},{},{},{},{},{},{},{},{},{},{},,{}\n",
+            index,
+            csv_field(&ssid),
+            point.x,
+            point.y,
+            point.z,
+            optional(net_info.and_then(|info| info.measured_power)),
+            optional(net_info.and_then(NetInfo::rssi_std_dev)),
+            optional(net_info.and_then(NetInfo::rssi_min)),
+            optional(net_info.and_then(NetInfo::rssi_max)),
+            net_info.map(|info| info.samples.len()).unwrap_or(0),
+            optional(net_info.and_then(|info| info.tx_power)),
+            optional(calc.estimate_distance(net_info)),
+            unit,
+        ));
+    }
+
+    if let Some(location) = &session.location {
+        csv.push_str(&format!(
+            "estimate,0,{},{},{},,,,,,,,,{},{}\n",
+            csv_field(&ssid),
+            location.x,
+            location.y,
+            location.uncertainty,
+            unit,
+        ));
+    }
+
+    return csv;
+}
+
+pub fn to_geojson(session: &Session) -> Result<String, ExportError> {
+    if !session.is_georeferenced() {
+        return Err(ExportError::NotGeoreferenced);
+    }
+    let calc = session.calculator();
+    let unit = session.unit.suffix();
+
+    let mut features = vec![];
+
+    for (index, point) in session.points.iter().enumerate() {
+        let (lat, lon) = session.to_geodetic(point.x, point.y).ok_or(ExportError::NotGeoreferenced)?;
+        let net_info = point.net_info.as_ref();
+
+        features.push(json!({
+            "type": "Feature",
+            "geometry": { "type": "Point", "coordinates": [lon, lat] },
+            "properties": {
+                "kind": "point",
+                "index": index,
+                "ssid": session.ssid,
+                "z": point.z,
+                "rssi_mean": net_info.and_then(|info| info.measured_power),
+                "rssi_std_dev": net_info.and_then(NetInfo::rssi_std_dev),
+                "rssi_min": net_info.and_then(NetInfo::rssi_min),
+                "rssi_max": net_info.and_then(NetInfo::rssi_max),
+                "samples": net_info.map(|info| info.samples.len()).unwrap_or(0),
+                "tx_power": net_info.and_then(|info| info.tx_power),
+                "distance": calc.estimate_distance(net_info),
+                "unit": unit,
+            },
+        }));
+    }
+
+    if let Some(location) = &session.location {
+        let (lat, lon) = session.to_geodetic(location.x, location.y).ok_or(ExportError::NotGeoreferenced)?;
+        let ring: Vec<Value> = uncertainty_ring(session).into_iter().map(|(lat, lon)| json!([lon, lat])).collect();
+
+        features.push(json!({
+            "type": "Feature",
+            "geometry": { "type": "Point", "coordinates": [lon, lat] },
+            "properties": { "kind": "estimate", "ssid": session.ssid, "uncertainty": location.uncertainty, "unit": unit },
+        }));
+        features.push(json!({
+            "type": "Feature",
+            "geometry": { "type": "Polygon", "coordinates": [ring] },
+            "properties": { "kind": "uncertainty", "ssid": session.ssid, "radius": location.uncertainty, "unit": unit },
+        }));
+    }
+
+    let collection = json!({ "type": "FeatureCollection", "features": features });
+
+    return Ok(serde_json::to_string_pretty(&collection).unwrap());
+}
+
+pub fn to_kml(session: &Session) -> Result<String, ExportError> {
+    if !session.is_georeferenced() {
+        return Err(ExportError::NotGeoreferenced);
+    }
+    let calc = session.calculator();
+    let unit = session.unit.suffix();
+    let ssid = xml_escape(&session.ssid.clone().unwrap_or_default());
+
+    let mut kml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<kml xmlns=\"http://www.opengis.net/kml/2.2\">\n<Document>\n");
+    kml.push_str(&format!("<name>{}</name>\n", ssid));
+
+    for (index, point) in session.points.iter().enumerate() {
+        let (lat, lon) = session.to_geodetic(point.x, point.y).ok_or(ExportError::NotGeoreferenced)?;
+        let net_info = point.net_info.as_ref();
+
+        kml.push_str(&format!("<Placemark>\n<name>Point {}</name>\n<ExtendedData>\n", index + 1));
+        kml_data(&mut kml, "z", point.z.to_string());
+        kml_data(&mut kml, "rssi_mean", optional(net_info.and_then(|info| info.measured_power)));
+        kml_data(&mut kml, "rssi_std_dev", optional(net_info.and_then(NetInfo::rssi_std_dev)));
+        kml_data(&mut kml, "rssi_min", optional(net_info.and_then(NetInfo::rssi_min)));
+        kml_data(&mut kml, "rssi_max", optional(net_info.and_then(NetInfo::rssi_max)));
+        kml_data(&mut kml, "samples", net_info.map(|info| info.samples.len()).unwrap_or(0).to_string());
+        kml_data(&mut kml, "tx_power", optional(net_info.and_then(|info| info.tx_power)));
+        kml_data(&mut kml, "distance", optional(calc.estimate_distance(net_info)));
+        kml_data(&mut kml, "unit", unit.to_string());
+        kml.push_str(&format!("</ExtendedData>\n<Point><coordinates>{},{}</coordinates></Point>\n</Placemark>\n", lon, lat));
+    }
+
+    if let Some(location) = &session.location {
+        let (lat, lon) = session.to_geodetic(location.x, location.y).ok_or(ExportError::NotGeoreferenced)?;
+        let ring: Vec<String> = uncertainty_ring(session).into_iter().map(|(lat, lon)| format!("{},{}", lon, lat)).collect();
+
+        kml.push_str(&format!("<Placemark>\n<name>Estimated {}</name>\n<ExtendedData>\n", ssid));
+        kml_data(&mut kml, "uncertainty", location.uncertainty.to_string());
+        kml_data(&mut kml, "unit", unit.to_string());
+        kml.push_str(&format!("</ExtendedData>\n<Point><coordinates>{},{}</coordinates></Point>\n</Placemark>\n", lon, lat));
+
+        kml.push_str("<Placemark>\n<name>Uncertainty</name>\n<Polygon><outerBoundaryIs><LinearRing><coordinates>\n");
+        kml.push_str(&ring.join(" "));
+        kml.push_str("\n</coordinates></LinearRing></outerBoundaryIs></Polygon>\n</Placemark>\n");
+    }
+
+    kml.push_str("</Document>\n</kml>\n");
+
+    return Ok(kml);
+}
+
+// Closed ring of (latitude, longitude) around the estimate, one uncertainty away from it
+fn uncertainty_ring(session: &Session) -> Vec<(f64, f64)> {
+    let Some(location) = &session.location else {
+        return vec![];
+    };
+
+    return (0..=UNCERTAINTY_SEGMENTS).filter_map(|i| {
+        let angle = std::f32::consts::TAU * i as f32 / UNCERTAINTY_SEGMENTS as f32;
+        let x = location.x + location.uncertainty * angle.cos();
+        let y = location.y + location.uncertainty * angle.sin();
+
+        session.to_geodetic(x, y)
+    }).collect();
+}
+
+fn kml_data(kml: &mut String, name: &str, value: String) {
+    kml.push_str(&format!("<Data name=\"{}\"><value>{}</value></Data>\n", name, value));
+}
+
+fn optional(value: Option<impl ToString>) -> String {
+    return value.map(|value| value.to_string()).unwrap_or_default();
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        return format!("\"{}\"", field.replace('"', "\"\""));
+    }
+
+    return field.to_string();
+}
+
+fn xml_escape(text: &str) -> String {
+    return text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geo::GeoOrigin;
+    use crate::models::{LinkReading, Location, Point};
+
+    // One point ten metres from the network at the default exponent, one untested, and an estimate
+    fn session(ssid: &str) -> Session {
+        let readings = [LinkReading { signal_level: -58.0, tx_power: -30.0 }, LinkReading { signal_level: -62.0, tx_power: -30.0 }];
+
+        return Session {
+            ssid: Some(String::from(ssid)),
+            points: vec![Point::new(0.0, 0.0, Some(NetInfo::from_readings(&readings))), Point::new(10.0, 0.0, None)],
+            location: Some(Location { x: 5.0, y: 5.0, uncertainty: 2.0 }),
+            ..Session::default()
+        };
+    }
+
+    fn georeferenced(ssid: &str) -> Session {
+        let mut session = session(ssid);
+        session.origin = Some(GeoOrigin::new(51.5, -0.1));
+
+        return session;
+    }
+
+    #[test]
+    fn writes_csv() {
+        let csv = to_csv(&session("Cafe, Upstairs"));
+        let lines: Vec<&str> = csv.lines().collect();
+
+        assert_eq!(lines, [
+            "kind,index,ssid,x,y,z,rssi_mean,rssi_std_dev,rssi_min,rssi_max,samples,tx_power,distance,uncertainty,unit",
+            "point,0,\"Cafe, Upstairs\",0,0,0,-60,2,-62,-58,2,-30,10,,m",
+            "point,1,\"Cafe, Upstairs\",10,0,0,,,,,0,,,,m",
+            "estimate,0,\"Cafe, Upstairs\",5,5,,,,,,,,,2,m",
+        ]);
+    }
+
+    #[test]
+    fn writes_geojson_longitude_first() {
+        let geojson: Value = serde_json::from_str(&to_geojson(&georeferenced("Home")).unwrap()).unwrap();
+        let features = geojson["features"].as_array().unwrap();
+        assert_eq!(features.len(), 4); // Two points, the estimate and its uncertainty
+
+        let coordinates = &features[0]["geometry"]["coordinates"];
+        assert!((coordinates[0].as_f64().unwrap() - -0.1).abs() < 1e-9);
+        assert!((coordinates[1].as_f64().unwrap() - 51.5).abs() < 1e-9);
+        assert_eq!(features[0]["properties"]["rssi_mean"], -60.0);
+
+        // The ring is closed
+        let ring = features[3]["geometry"]["coordinates"][0].as_array().unwrap();
+        assert_eq!(ring.len(), UNCERTAINTY_SEGMENTS + 1);
+        assert!((ring[0][0].as_f64().unwrap() - ring[UNCERTAINTY_SEGMENTS][0].as_f64().unwrap()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn escapes_kml() {
+        let kml = to_kml(&georeferenced("Tom & Jerry <5G>")).unwrap();
+
+        assert!(kml.contains("<name>Tom &amp; Jerry &lt;5G&gt;</name>"));
+        assert!(kml.contains("<name>Estimated Tom &amp; Jerry &lt;5G&gt;</name>"));
+        assert!(!kml.contains("Tom & Jerry"));
+        assert!(kml.contains("<Point><coordinates>-0.1,51.5</coordinates></Point>"));
+    }
+
+    #[test]
+    fn geographic_formats_need_an_origin() {
+        assert!(matches!(to_geojson(&session("Home")), Err(ExportError::NotGeoreferenced)));
+        assert!(matches!(to_kml(&session("Home")), Err(ExportError::NotGeoreferenced)));
+    }
+}
@@ -0,0 +1,123 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::units::DistanceUnit;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct FloorPlan {
+    pub path: PathBuf, // PNG or JPEG on disk
+    pub width_px: u32,
+    pub height_px: u32,
+    pub metres_per_pixel: f32, // Scale of the image, set by calibration
+}
+
+impl FloorPlan {
+    pub fn new(path: PathBuf, width_px: u32, height_px: u32, unit: DistanceUnit) -> FloorPlan {
+        // Until calibrated, stretch the image across the default 100 unit triangle
+        let metres_per_pixel = unit.to_metres(100.0) / width_px.max(1) as f32;
+
+        return FloorPlan { path, width_px, height_px, metres_per_pixel };
+    }
+
+    // Size of the image in plot units
+    pub fn size(&self, unit: DistanceUnit) -> [f32; 2] {
+        let units_per_pixel = unit.from_metres(self.metres_per_pixel);
+
+        return [self.width_px as f32 * units_per_pixel, self.height_px as f32 * units_per_pixel];
+    }
+
+    // Centre of the image in plot units, with its bottom left corner on the origin
+    pub fn center(&self, unit: DistanceUnit) -> [f32; 2] {
+        let [width, height] = self.size(unit);
+
+        return [width / 2.0, height / 2.0];
+    }
+}
+
+// Two points clicked on the floor plan, and the real distance between them
+pub struct Calibration {
+    pub points: Vec<[f64; 2]>,
+    pub distance: f32, // In the session's unit
+}
+
+impl Default for Calibration {
+    fn default() -> Self {
+        Self {
+            points: Vec::new(),
+            distance: 1.0,
+        }
+    }
+}
+
+impl Calibration {
+    pub fn add_point(&mut self, x: f64, y: f64) {
+        if self.points.len() == 2 {
+            self.points.clear();
+        }
+
+        self.points.push([x, y]);
+    }
+
+    pub fn is_complete(&self) -> bool {
+        return self.points.len() == 2 && self.plot_distance() > 0.0 && self.distance > 0.0;
+    }
+
+    fn plot_distance(&self) -> f32 {
+        if self.points.len() < 2 {
+            return 0.0;
+        }
+
+        let [x1, y1] = self.points[0];
+        let [x2, y2] = self.points[1];
+
+        return ((x2 - x1).powi(2) + (y2 - y1).powi(2)).sqrt() as f32;
+    }
+
+    // How much the plot has to be scaled by for the clicked points to be the entered distance apart
+    pub fn scale_factor(&self) -> Option<f32> {
+        if !self.is_complete() {
+            return None;
+        }
+
+        return Some(self.distance / self.plot_distance());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn calibration(points: &[[f64; 2]], distance: f32) -> Calibration {
+        let mut calibration = Calibration { distance, ..Calibration::default() };
+        for [x, y] in points {
+            calibration.add_point(*x, *y);
+        }
+
+        return calibration;
+    }
+
+    #[test]
+    fn scales_so_the_points_are_the_entered_distance_apart() {
+        let cases = [
+            (vec![[0.0, 0.0], [3.0, 4.0]], 10.0, Some(2.0)),
+            (vec![[10.0, 10.0], [10.0, 30.0]], 5.0, Some(0.25)),
+            (vec![[0.0, 0.0]], 10.0, None), // Only one point clicked
+            (vec![[2.0, 2.0], [2.0, 2.0]], 10.0, None), // Same spot twice
+            (vec![[0.0, 0.0], [3.0, 4.0]], 0.0, None),
+            (vec![[0.0, 0.0], [3.0, 4.0], [6.0, 8.0]], 10.0, None), // A third click starts over
+        ];
+
+        for (points, distance, expected) in cases {
+            assert_eq!(calibration(&points, distance).scale_factor(), expected, "{:?} {}", points, distance);
+        }
+    }
+
+    #[test]
+    fn sizes_the_image_in_plot_units() {
+        let floor_plan = FloorPlan::new(PathBuf::from("plan.png"), 500, 250, DistanceUnit::Metres);
+
+        assert_eq!(floor_plan.size(DistanceUnit::Metres), [100.0, 50.0]);
+        assert_eq!(floor_plan.center(DistanceUnit::Metres), [50.0, 25.0]);
+    }
+}
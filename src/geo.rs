@@ -0,0 +1,136 @@
+use serde::{Deserialize, Serialize};
+
+// WGS84 ellipsoid
+const SEMI_MAJOR_AXIS: f64 = 6_378_137.0;
+const ECCENTRICITY_SQ: f64 = 6.694_379_990_14e-3;
+
+// Where (0, 0) of a survey is on the globe. Survey coordinates are metres east (x) and north (y)
+// of it, in the local East-North-Up frame.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct GeoOrigin {
+    pub latitude: f64,  // Degrees
+    pub longitude: f64, // Degrees
+    #[serde(default)]
+    pub altitude: f64, // Metres above the ellipsoid
+}
+
+impl GeoOrigin {
+    pub fn new(latitude: f64, longitude: f64) -> GeoOrigin {
+        return GeoOrigin { latitude, longitude, altitude: 0.0 };
+    }
+
+    // Converts a local (east, north) offset in metres from the origin into (latitude, longitude)
+    pub fn to_geodetic(&self, east: f64, north: f64) -> (f64, f64) {
+        let (latitude, longitude, _) = self.enu_to_geodetic(east, north, 0.0);
+
+        return (latitude, longitude);
+    }
+
+    // Converts (latitude, longitude) into a local (east, north) offset in metres from the origin
+    pub fn to_local(&self, latitude: f64, longitude: f64) -> (f64, f64) {
+        let (east, north, _) = self.geodetic_to_enu(latitude, longitude, self.altitude);
+
+        return (east, north);
+    }
+
+    pub fn geodetic_to_enu(&self, latitude: f64, longitude: f64, altitude: f64) -> (f64, f64, f64) {
+        let [x, y, z] = geodetic_to_ecef(latitude, longitude, altitude);
+        let [x0, y0, z0] = geodetic_to_ecef(self.latitude, self.longitude, self.altitude);
+        let (dx, dy, dz) = (x - x0, y - y0, z - z0);
+
+        let (sin_lat, cos_lat) = self.latitude.to_radians().sin_cos();
+        let (sin_lon, cos_lon) = self.longitude.to_radians().sin_cos();
+
+        let east = -sin_lon * dx + cos_lon * dy;
+        let north = -sin_lat * cos_lon * dx - sin_lat * sin_lon * dy + cos_lat * dz;
+        let up = cos_lat * cos_lon * dx + cos_lat * sin_lon * dy + sin_lat * dz;
+
+        return (east, north, up);
+    }
+
+    pub fn enu_to_geodetic(&self, east: f64, north: f64, up: f64) -> (f64, f64, f64) {
+        let [x0, y0, z0] = geodetic_to_ecef(self.latitude, self.longitude, self.altitude);
+
+        let (sin_lat, cos_lat) = self.latitude.to_radians().sin_cos();
+        let (sin_lon, cos_lon) = self.longitude.to_radians().sin_cos();
+
+        let x = x0 - sin_lon * east - sin_lat * cos_lon * north + cos_lat * cos_lon * up;
+        let y = y0 + cos_lon * east - sin_lat * sin_lon * north + cos_lat * sin_lon * up;
+        let z = z0 + cos_lat * north + sin_lat * up;
+
+        return ecef_to_geodetic([x, y, z]);
+    }
+}
+
+fn geodetic_to_ecef(latitude: f64, longitude: f64, altitude: f64) -> [f64; 3] {
+    let (sin_lat, cos_lat) = latitude.to_radians().sin_cos();
+    let (sin_lon, cos_lon) = longitude.to_radians().sin_cos();
+
+    let normal_radius = SEMI_MAJOR_AXIS / (1.0 - ECCENTRICITY_SQ * sin_lat.powi(2)).sqrt();
+
+    return [
+        (normal_radius + altitude) * cos_lat * cos_lon,
+        (normal_radius + altitude) * cos_lat * sin_lon,
+        (normal_radius * (1.0 - ECCENTRICITY_SQ) + altitude) * sin_lat,
+    ];
+}
+
+// Iterative inverse, converges to well under a millimetre in a few rounds for anywhere near the surface
+fn ecef_to_geodetic([x, y, z]: [f64; 3]) -> (f64, f64, f64) {
+    let longitude = y.atan2(x);
+    let p = (x.powi(2) + y.powi(2)).sqrt();
+
+    let mut latitude = z.atan2(p * (1.0 - ECCENTRICITY_SQ));
+    let mut altitude = 0.0;
+
+    for _ in 0..5 {
+        let sin_lat = latitude.sin();
+        let normal_radius = SEMI_MAJOR_AXIS / (1.0 - ECCENTRICITY_SQ * sin_lat.powi(2)).sqrt();
+
+        altitude = p / latitude.cos() - normal_radius;
+        latitude = z.atan2(p * (1.0 - ECCENTRICITY_SQ * normal_radius / (normal_radius + altitude)));
+    }
+
+    return (latitude.to_degrees(), longitude.to_degrees(), altitude);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_enu() {
+        let origins = [GeoOrigin::new(51.5, -0.1), GeoOrigin { latitude: -33.9, longitude: 151.2, altitude: 120.0 }, GeoOrigin::new(78.2, 15.6)];
+
+        for origin in origins.iter() {
+            for (east, north, up) in [(0.0, 0.0, 0.0), (25.0, -40.0, 3.0), (-1500.0, 800.0, -10.0)] {
+                let (latitude, longitude, altitude) = origin.enu_to_geodetic(east, north, up);
+                let (back_east, back_north, back_up) = origin.geodetic_to_enu(latitude, longitude, altitude);
+
+                assert!((back_east - east).abs() < 1e-3, "east {} -> {}", east, back_east);
+                assert!((back_north - north).abs() < 1e-3, "north {} -> {}", north, back_north);
+                assert!((back_up - up).abs() < 1e-3, "up {} -> {}", up, back_up);
+            }
+        }
+    }
+
+    #[test]
+    fn moves_the_right_way() {
+        let origin = GeoOrigin::new(51.5, -0.1);
+
+        let (latitude, longitude) = origin.to_geodetic(0.0, 0.0);
+        assert!((latitude - 51.5).abs() < 1e-9 && (longitude - -0.1).abs() < 1e-9);
+
+        // A degree of latitude is about 111 km, so 100 m north is about 0.0009 degrees
+        let (latitude, longitude) = origin.to_geodetic(0.0, 100.0);
+        assert!((latitude - 51.5009).abs() < 0.00005 && (longitude - -0.1).abs() < 1e-9, "{}, {}", latitude, longitude);
+
+        let (_, longitude) = origin.to_geodetic(100.0, 0.0);
+        assert!(longitude > -0.1);
+
+        // Survey-sized offsets come back to well under a centimetre, despite the curvature
+        let (latitude, longitude) = origin.to_geodetic(60.0, -80.0);
+        let (east, north) = origin.to_local(latitude, longitude);
+        assert!((east - 60.0).abs() < 0.01 && (north - -80.0).abs() < 0.01, "({}, {})", east, north);
+    }
+}
@@ -0,0 +1,130 @@
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+
+pub const DEFAULT_ADDR: &str = "127.0.0.1:2947";
+
+// A position report from gpsd
+#[derive(Clone, Copy)]
+pub struct GpsFix {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub altitude: Option<f64>,
+}
+
+// The fields we need from a gpsd TPV (time-position-velocity) report
+#[derive(Deserialize)]
+struct Report {
+    class: String,
+    #[serde(default)]
+    mode: u8, // 0/1 no fix, 2 = 2D, 3 = 3D
+    lat: Option<f64>,
+    lon: Option<f64>,
+    #[serde(rename = "altHAE")]
+    alt_hae: Option<f64>,
+    alt: Option<f64>,
+}
+
+// Speaks gpsd's JSON protocol, so a gpsd simulator (e.g. gpsfake) works just as well as a receiver
+pub struct GpsdClient {
+    reader: BufReader<TcpStream>,
+}
+
+impl GpsdClient {
+    pub fn connect(addr: impl ToSocketAddrs) -> io::Result<GpsdClient> {
+        let mut stream = TcpStream::connect(addr)?;
+        stream.write_all(b"?WATCH={\"enable\":true,\"json\":true}\n")?;
+
+        return Ok(GpsdClient { reader: BufReader::new(stream) });
+    }
+
+    // Waits for the next report with a 2D or 3D fix
+    pub fn next_fix(&mut self, timeout: Duration) -> io::Result<GpsFix> {
+        let deadline = Instant::now() + timeout;
+        let mut line = String::new();
+
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(io::Error::new(io::ErrorKind::TimedOut, "no GPS fix from gpsd"));
+            }
+
+            self.reader.get_ref().set_read_timeout(Some(remaining))?;
+
+            line.clear();
+            if self.reader.read_line(&mut line)? == 0 {
+                return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "gpsd closed the connection"));
+            }
+
+            if let Some(fix) = parse_fix(&line) {
+                return Ok(fix);
+            }
+        }
+    }
+}
+
+// A fix from one line of gpsd's output, None for other reports and TPVs without a position
+fn parse_fix(line: &str) -> Option<GpsFix> {
+    let report = serde_json::from_str::<Report>(line).ok()?;
+
+    if let ("TPV", 2.., Some(latitude), Some(longitude)) = (report.class.as_str(), report.mode, report.lat, report.lon) {
+        return Some(GpsFix { latitude, longitude, altitude: report.alt_hae.or(report.alt) });
+    }
+
+    return None;
+}
+
+// One-off fix, for when there's no need to keep watching
+pub fn read_fix(addr: impl ToSocketAddrs, timeout: Duration) -> io::Result<GpsFix> {
+    return GpsdClient::connect(addr)?.next_fix(timeout);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+    use std::thread;
+
+    #[test]
+    fn parses_fixes() {
+        let cases = [
+            (r#"{"class":"TPV","mode":3,"lat":51.5,"lon":-0.1,"altHAE":45.2,"alt":0.0}"#, Some((51.5, -0.1, Some(45.2)))),
+            (r#"{"class":"TPV","mode":3,"lat":51.5,"lon":-0.1,"alt":11.0}"#, Some((51.5, -0.1, Some(11.0)))),
+            (r#"{"class":"TPV","mode":2,"lat":51.5,"lon":-0.1}"#, Some((51.5, -0.1, None))),
+            (r#"{"class":"TPV","mode":1,"lat":51.5,"lon":-0.1}"#, None), // No fix, whatever the last position was
+            (r#"{"class":"TPV","lat":51.5,"lon":-0.1}"#, None),
+            (r#"{"class":"TPV","mode":3,"lat":51.5}"#, None),
+            (r#"{"class":"SKY","mode":3,"lat":51.5,"lon":-0.1}"#, None),
+            ("not json", None),
+        ];
+
+        for (line, expected) in cases {
+            let fix = parse_fix(line).map(|fix| (fix.latitude, fix.longitude, fix.altitude));
+            assert_eq!(fix, expected, "{}", line);
+        }
+    }
+
+    #[test]
+    fn waits_for_a_fix() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let gpsd = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut watch = String::new();
+            BufReader::new(stream.try_clone().unwrap()).read_line(&mut watch).unwrap();
+
+            stream.write_all(b"{\"class\":\"VERSION\",\"release\":\"3.25\"}\n").unwrap();
+            stream.write_all(b"{\"class\":\"TPV\",\"mode\":1}\n").unwrap();
+            stream.write_all(b"{\"class\":\"TPV\",\"mode\":3,\"lat\":-33.9,\"lon\":151.2,\"altHAE\":30.0}\n").unwrap();
+
+            return watch;
+        });
+
+        let fix = read_fix(addr, Duration::from_secs(2)).unwrap();
+        assert_eq!((fix.latitude, fix.longitude, fix.altitude), (-33.9, 151.2, Some(30.0)));
+        assert!(gpsd.join().unwrap().starts_with("?WATCH="));
+    }
+}
@@ -0,0 +1,2274 @@
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use crate::capture::{self, BeaconSample, LiveCapture};
+use crate::device_profile::{DeviceProfile, DeviceProfiles};
+use crate::export::ExportFormat;
+use crate::floor_plan::{Calibration, FloorPlan};
+use crate::geo::GeoOrigin;
+use crate::gpsd;
+use crate::history::{self, Edit, History};
+use crate::interfaces::{self, WirelessInterface};
+use crate::logging::Logging;
+use crate::models::{Location, NetInfo, Point, Wall};
+use crate::network_list::{self, NetworkColumn};
+use crate::network_manager::{self, BackendError, NetworkManager, Network};
+use crate::notifications::{Notifications, Problem, Severity};
+use crate::particle_filter::ParticleFilter;
+use crate::rssi_monitor::RssiMonitor;
+use crate::secrets::{SecretServiceStore, SecretStore};
+use crate::security::{Credentials, EapMethod, EnterpriseCredentials, Phase2Auth, SecurityKind};
+use crate::sensor::{self, Collector};
+use crate::session::Session;
+use crate::settings::Settings;
+use crate::tracking::Tracker;
+use crate::trilateration_calc::{self, TrilaterationCalculator};
+use crate::units::DistanceUnit;
+
+use eframe::{*};
+use eframe::egui::{self, Event, Key, KeyboardShortcut, Modifiers, Vec2};
+
+use tracing::{debug, info, Level};
+use zeroize::Zeroizing;
+
+use egui_plot::{Legend, Line, LineStyle, MarkerShape, Plot, PlotImage, PlotPoint, PlotPoints, PlotTransform, PlotUi, Points, Polygon, Text};
+
+use egui::{Button, Color32, ColorImage, DragValue, RichText, Stroke, TextEdit, TextureHandle, TextureOptions, Theme, ViewportCommand};
+
+const RANGE_SEGMENTS: usize = 64; // Vertices used to draw each range circle
+const GPS_TIMEOUT: Duration = Duration::from_secs(3);
+const SETTINGS_KEY: &str = "settings"; // Where the settings are kept in eframe's storage
+const SCAN_INTERVAL: Duration = Duration::from_secs(3); // How often the network list is refreshed while choosing one
+const ESTIMATE_ANIMATION: f32 = 0.4; // Seconds the estimate takes to glide to a recomputed position
+const SWEEP_EXPONENTS: (f32, f32) = (2.0, 5.0); // Range the sensitivity view sweeps the path loss exponent over
+const SWEEP_STEPS: usize = 30;
+
+pub struct TriangleGator {
+    network_manager: network_manager::NetworkManager,
+    trilat_calc: trilateration_calc::TrilaterationCalculator,
+
+    network_password: Zeroizing<String>, // Network password (if there is one), wiped once it's been used
+    secret_store: Box<dyn SecretStore>,
+    enterprise: EnterpriseCredentials, // 802.1X login, its password wiped with the network password
+    ca_cert_path: String, // CA certificate path, as typed by the user
+    hidden_ssid: String, // SSID of a hidden network to add, as typed by the user
+    hidden_kind: SecurityKind,
+
+    session: Session, // Triangle points, path loss exponent and calculated location of network
+    solved_session: Option<Session>, // Session as it was when last calculated, kept for export
+    solve_key: Vec<Option<f32>>, // Solver inputs at the last calculation, to tell when it's out of date
+    sweep: Vec<(f32, Location)>, // Estimate at each exponent across the sweep
+    show_sweep: bool, // Draw the sweep on the plot
+    selected_point: Option<usize>, // Index of selected point
+    dragged_point: Option<usize>, // Index of point being dragged around the plot
+    drag_origin: [f32; 3], // Where the dragged point started, to undo the drag
+    drag_offset: [f32; 2], // From the pointer to the dragged point, so grabbing it off centre doesn't make it jump
+    history: History, // Edits to the session, for undo and redo
+    context_point: Option<usize>, // Index of point the context menu was opened on
+    context_position: [f64; 2], // Where on the plot the context menu was opened
+
+    floor_plan_path: String, // Floor plan image path, as typed by the user
+    floor_plan_texture: Option<TextureHandle>, // Loaded floor plan image
+    calibration: Option<Calibration>, // Floor plan calibration in progress
+
+    rssi_monitor: RssiMonitor, // Live signal of the connected network
+
+    capture_path: String, // pcap/pcapng file path, as typed by the user
+    beacons: Vec<BeaconSample>, // Beacons heard since the last point was measured from them
+    live_capture: Option<LiveCapture>, // Monitor mode capture in progress
+    beacon_bssid: Option<String>, // Access point to measure from the beacons
+
+    collector: Option<Collector>, // Receives readings from sensor agents
+    live_solve: bool, // Solve the sensors' readings every frame
+
+    tracker: Tracker, // Filtered track of a moving network
+    tracking: bool, // Feed measurements to the tracker, and draw its track instead of the calculated location
+    tracking_started: Instant,
+    last_sensor_track: Instant, // When the sensors' readings were last fed to the tracker
+
+    particle_filter: ParticleFilter, // Estimate for heavy-tailed or ambiguous readings
+    particles: bool, // Feed measurements to the particle filter, and draw its cloud
+    drawing_walls: bool, // Clicks on the plot add walls instead of points
+    wall_start: Option<[f64; 2]>, // First corner of the wall being drawn
+
+    interfaces: Vec<WirelessInterface>, // Wireless adapters to pick from
+    reference_rssi: f32, // Reference device's reading, to calibrate this one against
+    profile_draft: Option<DeviceProfile>, // Device profile being edited, applied to readings on Calibrate or Save
+
+    logging: Logging, // Records for the log panel, and the optional log file
+    notifications: Notifications, // Toasts, and banners for problems that block the survey
+    last_scan: Option<Instant>, // When the network list was last refreshed
+    log_level: Level, // Least severe level shown in the log panel
+    log_filter: String, // Only show records containing this, as typed by the user
+    network_filter: String, // Only list networks matching this, as typed by the user
+
+    settings: Settings, // Preferences saved between launches
+}
+
+impl Default for TriangleGator {
+    fn default() -> Self {
+        let mut network_manager = NetworkManager::default();
+        network_manager.load_device_profile();
+
+        Self {
+            network_manager,
+            trilat_calc: TrilaterationCalculator::default(),
+
+            network_password: Zeroizing::default(),
+            secret_store: Box::new(SecretServiceStore),
+            enterprise: EnterpriseCredentials::default(),
+            ca_cert_path: String::from(""),
+            hidden_ssid: String::from(""),
+            hidden_kind: SecurityKind::WpaPsk,
+
+            session: Session::default(),
+            solved_session: None,
+            solve_key: Vec::new(),
+            sweep: Vec::new(),
+            show_sweep: false,
+            selected_point: None,
+            dragged_point: None,
+            drag_origin: [0.0, 0.0, 0.0],
+            drag_offset: [0.0, 0.0],
+            history: History::default(),
+            context_point: None,
+            context_position: [0.0, 0.0],
+
+            floor_plan_path: String::from(""),
+            floor_plan_texture: None,
+            calibration: None,
+
+            rssi_monitor: RssiMonitor::default(),
+
+            capture_path: String::from(""),
+            beacons: Vec::new(),
+            live_capture: None,
+            beacon_bssid: None,
+
+            collector: None,
+            live_solve: false,
+
+            tracker: Tracker::default(),
+            tracking: false,
+            tracking_started: Instant::now(),
+            last_sensor_track: Instant::now(),
+
+            particle_filter: ParticleFilter::default(),
+            particles: false,
+            drawing_walls: false,
+            wall_start: None,
+
+            interfaces: interfaces::wireless_interfaces(),
+            reference_rssi: -50.0,
+            profile_draft: None,
+
+            logging: Logging::default(),
+            notifications: Notifications::default(),
+            last_scan: None,
+            log_level: Level::INFO,
+            log_filter: String::from(""),
+            network_filter: String::from(""),
+
+            settings: Settings::default(),
+        }
+    }
+}
+
+impl TriangleGator {
+    // Picks up the settings saved last time, and shows what the subscriber installed by logging::init records
+    pub fn new(cc: &CreationContext<'_>, logging: Logging) -> Self {
+        let settings: Settings = cc.storage.and_then(|storage| get_value(storage, SETTINGS_KEY)).unwrap_or_default();
+
+        let mut triangle_gator = Self { logging, ..Default::default() };
+        apply_settings(&mut triangle_gator, settings);
+        check_backend(&mut triangle_gator);
+
+        return triangle_gator;
+    }
+}
+
+impl App for TriangleGator {        
+    fn clear_color(&self, _visuals: &egui::Visuals) -> [f32; 4] {
+        egui::Rgba::TRANSPARENT.to_array() // Make sure we don't paint anything behind the rounded corners
+    }
+
+    fn save(&mut self, storage: &mut dyn Storage) {
+        // Kept on the session and backend while running, only copied over to be saved
+        self.settings.path_loss_exponent = self.session.path_loss_exponent;
+        self.settings.unit = self.session.unit;
+        self.settings.interface = self.network_manager.get_interface().map(String::from);
+
+        set_value(storage, SETTINGS_KEY, &self.settings);
+    }
+
+    // Put the user back on their own network rather than leaving them on the surveyed one
+    fn on_exit(&mut self, _gl: Option<&glow::Context>) {
+        self.network_manager.restore_connection();
+    }
+
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut Frame) {
+        if !self.network_manager.get_connection_status() && self.last_scan.is_none_or(|last_scan| last_scan.elapsed() >= SCAN_INTERVAL) {
+            scan_networks(self);
+        }
+        if !self.network_manager.get_connection_status() {
+            ctx.request_repaint_after(SCAN_INTERVAL);
+        }
+
+        background_work(ctx, self);
+        toasts_ui(ctx, self);
+        undo_shortcuts(ctx, self);
+        live_recompute(self);
+
+        custom_window_frame(ctx, "Triangle Gator", |ui| {
+            ctx.set_theme(Theme::Dark);
+
+            banners_ui(ui, self);
+            panel_toggles_ui(ui, self);
+
+            let connected = self.network_manager.get_selected_network().is_some() && self.network_manager.get_connection_status();
+
+            // Side and bottom panels go first, the plot gets whatever space is left
+            egui::SidePanel::left("networks_panel")
+                .resizable(true)
+                .default_width(380.0)
+                .width_range(240.0..=640.0)
+                .show_animated_inside(ui, self.settings.show_networks_panel, |ui| {
+                    egui::ScrollArea::vertical().show(ui, |ui| {
+                        networks_panel_ui(ui, self);
+                    });
+                });
+
+            egui::SidePanel::right("survey_panel")
+                .resizable(true)
+                .default_width(300.0)
+                .width_range(220.0..=520.0)
+                .show_animated_inside(ui, connected && self.settings.show_survey_panel, |ui| {
+                    egui::ScrollArea::vertical().show(ui, |ui| {
+                        survey_ui(ui, self);
+                        ui.separator();
+                        tools_ui(ui, self);
+                    });
+                });
+
+            egui::TopBottomPanel::bottom("bottom_panel")
+                .resizable(true)
+                .default_height(180.0)
+                .height_range(80.0..=480.0)
+                .show_animated_inside(ui, self.settings.show_bottom_panel, |ui| {
+                    egui::ScrollArea::vertical().show(ui, |ui| {
+                        if connected {
+                            rssi_monitor_ui(ui, self);
+                        }
+                        log_ui(ui, self);
+                    });
+                });
+
+            egui::CentralPanel::default().frame(egui::Frame::NONE).show_inside(ui, |ui| {
+                if connected {
+                    plot_panel_ui(ui, self);
+                } else {
+                    ui.centered_and_justified(|ui| {
+                        ui.label("Choose a network on the left to start surveying");
+                    });
+                }
+            });
+        });
+    }
+}
+
+// Monitoring, capture and sensor collection, kept running whether or not their panels are showing
+fn background_work(ctx: &egui::Context, selph: &mut TriangleGator) {
+    // The monitor corrects its readings with whatever profile is current
+    selph.rssi_monitor.device_profile = selph.network_manager.get_device_profile().cloned();
+    selph.rssi_monitor.poll();
+
+    if selph.rssi_monitor.is_running() {
+        ctx.request_repaint_after(Duration::from_millis(selph.rssi_monitor.get_rate_ms()));
+    }
+
+    if let Some(live_capture) = &selph.live_capture {
+        let mut beacons = live_capture.poll();
+
+        if let Some(device_profile) = selph.network_manager.get_device_profile() {
+            for beacon in beacons.iter_mut() {
+                beacon.signal_dbm = device_profile.correct_signal(beacon.signal_dbm);
+            }
+        }
+
+        selph.beacons.extend(beacons);
+        ctx.request_repaint_after(Duration::from_millis(250));
+    }
+
+    let Some(collector) = &selph.collector else {
+        return;
+    };
+
+    if let Some(e) = collector.take_last_error() {
+        selph.notifications.error(format!("Sensor report rejected: {}", e));
+    }
+
+    if selph.live_solve {
+        let mut live_session = Session {
+            points: collector.snapshot(),
+            path_loss_exponent: selph.session.path_loss_exponent,
+            unit: selph.session.unit,
+            ssid: selph.network_manager.get_selected_network().as_ref().map(|network| network.ssid.clone()),
+            origin: selph.session.origin.clone(),
+            floor_plan: selph.session.floor_plan.clone(),
+            ..Session::default()
+        };
+
+        if live_session.solve().is_some() {
+            selph.session.location = live_session.location.clone();
+            selph.solved_session = Some(live_session);
+        }
+    }
+
+    // Sensors report continuously, so hand the tracker one averaged snapshot a second
+    if (selph.tracking || selph.particles) && selph.last_sensor_track.elapsed() >= Duration::from_secs(1) {
+        let snapshot = collector.snapshot();
+        feed_measurements(selph, &snapshot);
+        selph.last_sensor_track = Instant::now();
+    }
+
+    ctx.request_repaint_after(Duration::from_millis(250));
+}
+
+// Show or hide each panel, the choice is saved with the settings
+fn panel_toggles_ui(ui: &mut egui::Ui, selph: &mut TriangleGator) {
+    ui.horizontal(|ui| {
+        ui.toggle_value(&mut selph.settings.show_networks_panel, "Networks");
+        ui.toggle_value(&mut selph.settings.show_survey_panel, "Survey");
+        ui.toggle_value(&mut selph.settings.show_bottom_panel, "Log");
+
+        ui.separator();
+        undo_buttons_ui(ui, selph);
+    });
+}
+
+// Picking and joining a network, then the settings
+fn networks_panel_ui(ui: &mut egui::Ui, selph: &mut TriangleGator) {
+    match selph.network_manager.get_selected_network() {
+        None => network_list_ui(ui, selph),
+        Some(network) if selph.network_manager.get_connection_status() => {
+            ui.label(format!("Connected to '{}'", network.ssid));
+        }
+        Some(_) => connect_ui(ui, selph),
+    }
+
+    ui.separator();
+    settings_ui(ui, selph);
+
+    ui.add_space(8.0);
+    ui.hyperlink_to("Open Source Project by Leonardo Lees", "https://github.com/LeoL6/triangle-gator");
+}
+
+fn network_list_ui(ui: &mut egui::Ui, selph: &mut TriangleGator) {
+    interface_ui(ui, selph);
+
+    ui.add(TextEdit::singleline(&mut selph.network_filter).hint_text("Filter by SSID, security, band or BSSID"));
+
+    let mut selected_network = network_table_ui(ui, selph);
+
+    if let Some(hidden_network) = hidden_network_ui(ui, selph) {
+        selected_network = Some(hidden_network);
+    }
+
+    if let Some(selected_network) = selected_network {
+        // Fill in the password if one was remembered
+        let password = match selph.secret_store.lookup(&selected_network.ssid) {
+            Ok(password) => password.unwrap_or_default(),
+            Err(e) => {
+                selph.notifications.error(format!("Couldn't look up a saved password: {}. Type it in instead.", e));
+                Zeroizing::default()
+            }
+        };
+
+        if selected_network.kind == SecurityKind::Enterprise {
+            selph.enterprise.password = password;
+        } else {
+            selph.network_password = password;
+        }
+        selph.network_manager.select_network(&selected_network);
+    }
+}
+
+// Scanned networks, one row per SSID. Returns the one clicked, if any.
+fn network_table_ui(ui: &mut egui::Ui, selph: &mut TriangleGator) -> Option<Network> {
+    let mut networks: Vec<&Network> = selph.network_manager.get_available_networks().iter()
+        .filter(|network| network_list::matches_filter(network, &selph.network_filter))
+        .collect();
+    network_list::sort(&mut networks, selph.settings.network_sort, selph.settings.network_sort_ascending);
+
+    let mut selected_network = None;
+    let mut sort_by = None;
+
+    egui::ScrollArea::both()
+    .max_height(260.0)
+    .show(ui, |ui| {
+        egui::Grid::new("networks").striped(true).num_columns(NetworkColumn::ALL.len()).show(ui, |ui| {
+            for column in NetworkColumn::ALL {
+                let sorted = selph.settings.network_sort == column;
+                let arrow = if !sorted { "" } else if selph.settings.network_sort_ascending { " ⬆" } else { " ⬇" };
+
+                if ui.selectable_label(sorted, format!("{}{}", column, arrow)).clicked() {
+                    sort_by = Some(column);
+                }
+            }
+            ui.end_row();
+
+            for network in &networks {
+                signal_ui(ui, network.measured_power);
+
+                let icon = if network.kind.needs_password() { "🔒" } else { "🔓" };
+                ui.label(format!("{} {}", icon, network.kind));
+
+                // Suspicious networks stand out most, then ones with several access points
+                let suspicions = network_list::suspicions(network);
+                let ssid = if suspicions.is_empty() {
+                    RichText::new(&network.ssid)
+                } else {
+                    RichText::new(format!("⚠ {}", network.ssid)).color(ui.visuals().warn_fg_color)
+                };
+                let ssid = if suspicions.is_empty() && network.bssids.len() > 1 { ssid.color(ui.visuals().hyperlink_color) } else { ssid };
+
+                let mut hover = network.bssids.join("\n");
+                for suspicion in &suspicions {
+                    hover.push_str(&format!("\n⚠ {}", suspicion));
+                }
+
+                if ui.button(ssid).on_hover_text(hover).clicked() {
+                    selected_network = Some(Network::from(network));
+                }
+
+                ui.label(match (network.band(), network.channel) {
+                    (Some(band), Some(channel)) => format!("{} ch {}", band, channel),
+                    (Some(band), None) => band.to_string(),
+                    (None, Some(channel)) => format!("ch {}", channel),
+                    (None, None) => String::from("-"),
+                });
+                ui.label(format!("{}", network.bssids.len()));
+                ui.end_row();
+            }
+        });
+    });
+
+    // Clicking the sorted column again flips it
+    if let Some(column) = sort_by {
+        if selph.settings.network_sort == column {
+            selph.settings.network_sort_ascending = !selph.settings.network_sort_ascending;
+        } else {
+            selph.settings.network_sort = column;
+            selph.settings.network_sort_ascending = column.default_ascending();
+        }
+    }
+
+    return selected_network;
+}
+
+// Signal bars, then the quality nmcli reports and roughly what that is in dBm
+fn signal_ui(ui: &mut egui::Ui, percent: u32) {
+    ui.horizontal(|ui| {
+        let (rect, _) = ui.allocate_exact_size(egui::vec2(18.0, 12.0), egui::Sense::hover());
+        let bars = network_list::signal_bars(percent);
+        let color = match bars {
+            3 | 4 => Color32::GREEN,
+            2 => Color32::YELLOW,
+            _ => Color32::RED,
+        };
+
+        for bar in 0..4 {
+            let height = rect.height() * (bar + 1) as f32 / 4.0;
+            let left = rect.left() + bar as f32 * 4.5;
+            let bar_rect = egui::Rect::from_min_max(egui::pos2(left, rect.bottom() - height), egui::pos2(left + 3.0, rect.bottom()));
+            let fill = if bar < bars { color } else { ui.visuals().widgets.inactive.bg_fill };
+
+            ui.painter().rect_filled(bar_rect, 0.0, fill);
+        }
+
+        ui.label(format!("{}% ~{} dBm", percent, network_list::percent_to_dbm(percent)));
+    });
+}
+
+// Credentials for the selected network
+fn connect_ui(ui: &mut egui::Ui, selph: &mut TriangleGator) {
+    ui.vertical_centered(|ui| {
+        let selected_network = selph.network_manager.get_selected_network().as_ref().unwrap();
+
+        let hidden = if selected_network.hidden { " (hidden)" } else { "" };
+        ui.label(format!("{}{}\n{}", selected_network.ssid, hidden, selected_network.kind));
+
+        let kind = selected_network.kind;
+        let ssid = selected_network.ssid.clone();
+
+        credentials_ui(ui, selph, kind);
+
+        if ui.button("Connect").clicked() {
+            let credentials = credentials(selph, kind);
+            let result = selph.network_manager.connect_to_network(&credentials);
+            selph.network_manager.is_connected(result.is_ok());
+
+            match result {
+                Ok(()) => {
+                    selph.notifications.success(format!("Connected to '{}'", ssid));
+
+                    if selph.settings.remember_password && !credentials.password().is_empty() {
+                        if let Err(e) = selph.secret_store.store(&ssid, credentials.password()) {
+                            selph.notifications.error(format!("Couldn't remember the password: {}. Is a keyring unlocked?", e));
+                        }
+                    }
+
+                    selph.network_password = Zeroizing::default();
+                    selph.enterprise.password = Zeroizing::default();
+                }
+                Err(BackendError::MissingTool(tool)) => selph.notifications.raise(Problem::MissingTool(tool)),
+                Err(e) => selph.notifications.error(format!("Couldn't connect to '{}': {}. Check the credentials and that the network is in range.", ssid, e)),
+            }
+        }
+
+        // if ui.button("Test").clicked() {
+        //     selph.network_manager.is_connected(true);
+        // }
+    });
+}
+
+// Measuring points and solving for the network
+fn survey_ui(ui: &mut egui::Ui, selph: &mut TriangleGator) {
+    let ready_to_scan = selph.network_manager.ready_to_calc(&selph.session.points, &selph.session.calculator());
+
+    ui.columns(3, |ui| {
+        ui[0].vertical_centered(|ui| {
+            if ui.add_enabled(selph.selected_point.is_some(), Button::new("Test Point")).clicked() {
+                let net_info = get_selected_netinfo(&selph.network_manager, selph.settings.sample_scale, selph.settings.sample_length);
+
+                // A failed reading isn't kept, so the point doesn't look tested
+                if net_info.measured_power.is_none() {
+                    let interface = selph.network_manager.get_interface().unwrap_or("the interface").to_string();
+                    selph.notifications.error(format!("No signal readings from iwconfig. Check that {} is still connected.", interface));
+                } else {
+                    measure_point(selph, selph.selected_point.unwrap(), Some(net_info));
+                    selph.selected_point = None;
+                }
+            }
+        });
+
+        ui[1].vertical_centered(|ui| {
+            if ui.add_enabled(ready_to_scan, Button::new("Calculate")).on_hover_text("Solve now, then again whenever the exponent, points or readings change").clicked() {
+                if let Some(location) = solve_session(selph) {
+                    info!(uncertainty = location.uncertainty, "Estimated network location: ({:.2}, {:.2}) {}", location.x, location.y, selph.session.unit.suffix());
+                }
+            }
+        });
+
+        ui[2].vertical_centered(|ui| {
+            let reset_hint = match selph.network_manager.get_original_connection() {
+                Some(original) => format!("Disconnect and reconnect to '{}'", original.name),
+                None => String::from("Disconnect and start over"),
+            };
+
+            if ui.button("Reset").on_hover_text(format!("{}. Readings can be brought back with Undo.", reset_hint)).clicked() {
+                let before = selph.session.clone();
+                reset_calc(selph);
+                selph.history.record(Edit::Replace { description: "reset", before: Box::new(before), after: Box::new(selph.session.clone()) });
+                scan_networks(selph);
+            }
+        });
+    });
+
+    if let Some(selected_point) = selph.selected_point {
+        ui.vertical_centered(|ui| {
+            let from = history::position(&selph.session.points[selected_point]);
+            point_inspector_ui(ui, &mut selph.session.points[selected_point], selph.session.unit, selph.session.origin.as_ref());
+            let to = history::position(&selph.session.points[selected_point]);
+
+            if from != to {
+                selph.history.record(Edit::MovePoint { index: selected_point, from, to });
+            }
+        });
+
+        ui.columns(2, |ui| {
+            ui[0].vertical_centered(|ui| {
+                ui.label("Sample Scale");
+                ui.add(DragValue::new(&mut selph.settings.sample_scale).speed(1).range(RangeInclusive::new(1, 20)));
+            });
+
+            ui[1].vertical_centered(|ui| {
+                ui.label("Sample Length");
+                ui.add(DragValue::new(&mut selph.settings.sample_length).speed(1).range(RangeInclusive::new(1, 2000)));
+            });
+        });
+    }
+
+    points_ui(ui, selph);
+
+    if ready_to_scan {
+        ui.vertical_centered(|ui| {
+            ui.label("Path Loss Exponent");
+            let from = selph.session.path_loss_exponent;
+            if ui.add(DragValue::new(&mut selph.session.path_loss_exponent).speed(0.1).range(RangeInclusive::new(2.0, 5.0))).changed() {
+                selph.history.record(Edit::SetExponent { from, to: selph.session.path_loss_exponent });
+            }
+        });
+    }
+
+    if let Some(location) = &selph.session.location {
+        let unit = selph.session.unit.suffix();
+
+        ui.vertical_centered(|ui| {
+            ui.label(format!("Estimate: ({:.2}, {:.2}) {}  ± {:.2} {}", location.x, location.y, unit, location.uncertainty, unit));
+        });
+    }
+
+    if selph.solved_session.is_some() {
+        sensitivity_ui(ui, selph);
+    }
+
+    if let Some(solved_session) = &selph.solved_session {
+        // Georeferencing can be set up after calculating, export with the current origin
+        let mut solved_session = solved_session.clone();
+        solved_session.origin = selph.session.origin.clone();
+
+        ui.horizontal(|ui| {
+            ui.add(TextEdit::singleline(&mut selph.settings.export_path).desired_width(100.0).hint_text("export path"));
+
+            for format in [ExportFormat::Csv, ExportFormat::GeoJson, ExportFormat::Kml] {
+                let enabled = !format.needs_origin() || solved_session.is_georeferenced();
+
+                if ui.add_enabled(enabled, Button::new(format.extension())).clicked() {
+                    let path = PathBuf::from(&selph.settings.export_path).with_extension(format.extension());
+
+                    match solved_session.export(format, &path) {
+                        Ok(()) => selph.notifications.success(format!("Exported survey to {}", path.display())),
+                        Err(e) => selph.notifications.error(format!("Failed to export {}: {}. Check the folder exists and is writable.", path.display(), e)),
+                    }
+                }
+            }
+        });
+    }
+}
+
+// Everything else that feeds or refines the survey
+fn tools_ui(ui: &mut egui::Ui, selph: &mut TriangleGator) {
+    units_ui(ui, selph);
+    floor_plan_ui(ui, selph);
+    georeference_ui(ui, selph);
+    device_profile_ui(ui, selph);
+    beacon_capture_ui(ui, selph);
+    sensor_ui(ui, selph);
+    tracking_ui(ui, selph);
+    particle_filter_ui(ui, selph);
+}
+
+// Every point, to pick one without finding it on the plot
+fn points_ui(ui: &mut egui::Ui, selph: &mut TriangleGator) {
+    let unit = selph.session.unit.suffix();
+
+    ui.collapsing(format!("Points ({})", selph.session.points.len()), |ui| {
+        let mut toggled = None;
+
+        egui::Grid::new("points").striped(true).num_columns(4).show(ui, |ui| {
+            ui.label("#");
+            ui.label(format!("Position ({})", unit));
+            ui.label("RSSI");
+            ui.label("Use");
+            ui.end_row();
+
+            for (index, point) in selph.session.points.iter().enumerate() {
+                let selected = selph.selected_point == Some(index);
+
+                if ui.selectable_label(selected, format!("{}", index + 1)).clicked() {
+                    selph.selected_point = if selected { None } else { Some(index) };
+                }
+                ui.label(format!("({:.2}, {:.2}, {:.2})", point.x, point.y, point.z));
+                ui.label(match point.net_info.as_ref().and_then(|net_info| net_info.measured_power) {
+                    Some(measured_power) => format!("{:.1} dBm", measured_power),
+                    None => String::from("untested"),
+                });
+
+                let mut used = !point.excluded;
+                if ui.checkbox(&mut used, "").on_hover_text("Use this point's reading in the solve").changed() {
+                    toggled = Some((index, !used));
+                }
+                ui.end_row();
+            }
+        });
+
+        if let Some((index, excluded)) = toggled {
+            selph.history.apply(&mut selph.session, Edit::SetExcluded { index, excluded });
+        }
+    });
+}
+
+// The survey plot, filling the central panel
+fn plot_panel_ui(ui: &mut egui::Ui, selph: &mut TriangleGator) {
+    egui::Frame::NONE
+        .stroke(egui::Stroke::new(1.0, egui::Color32::GRAY)) // Border thickness and color
+        .outer_margin(egui::Margin::same(8)) // Margin outside the border
+        .inner_margin(egui::Margin::same(6)) // Margin inside of the border
+        .corner_radius(5.0) // Rounded Corners
+        .fill(egui::Color32::from_black_alpha(0)) // Clear Background
+        .show(ui, |ui| {
+            let (scroll, pointer_down, pointer_pressed, pointer_clicked, secondary_clicked, modifiers) = ui.input(|i| {
+                let scroll = i.events.iter().find_map(|e| match e {
+                    Event::MouseWheel {
+                        unit: _,
+                        delta,
+                        modifiers: _,
+                    } => Some(*delta),
+                    _ => None,
+                });
+                (scroll, i.pointer.primary_down(), i.pointer.primary_pressed(), i.pointer.primary_clicked(), i.pointer.secondary_clicked(), i.modifiers)
+            });
+
+            // Glides to a recomputed estimate rather than jumping
+            let estimate = selph.session.location.as_ref().map(|location| [
+                ui.ctx().animate_value_with_time(egui::Id::new("estimate_x"), location.x, ESTIMATE_ANIMATION),
+                ui.ctx().animate_value_with_time(egui::Id::new("estimate_y"), location.y, ESTIMATE_ANIMATION),
+            ]);
+
+            let plot_response = Plot::new("plot")
+            .allow_zoom(false)
+            .allow_drag(false)
+            .allow_scroll(false)
+            .show_axes(true)
+            .x_axis_label(format!("x ({})", selph.session.unit.suffix()))
+            .y_axis_label(format!("y ({})", selph.session.unit.suffix()))
+            .legend(Legend::default())
+            .data_aspect(1.0) // Keep units square so floor plans aren't stretched
+            .show(ui, |plot_ui| {
+                let pointer_coordinate = plot_ui.pointer_coordinate();
+                let hovered = pointer_coordinate.and_then(|pointer_pos| hovered_point(&selph.session.points, pointer_pos, plot_ui.transform()));
+                let plot_hovered = plot_ui.response().hovered();
+
+                if !pointer_down {
+                    // Dropped, so the whole drag undoes as one move
+                    if let Some(index) = selph.dragged_point.take() {
+                        let to = history::position(&selph.session.points[index]);
+
+                        if to != selph.drag_origin {
+                            selph.history.record(Edit::MovePoint { index, from: selph.drag_origin, to });
+                        }
+                    }
+                } else if pointer_pressed && plot_hovered && selph.calibration.is_none() && !selph.drawing_walls {
+                    selph.dragged_point = hovered;
+
+                    if let (Some(index), Some(pointer_pos)) = (hovered, pointer_coordinate) {
+                        let point = &selph.session.points[index];
+                        selph.drag_origin = history::position(point);
+                        selph.drag_offset = [point.x - pointer_pos.x as f32, point.y - pointer_pos.y as f32];
+                    }
+                }
+
+                if let Some(mut scroll) = scroll {
+                    if modifiers.ctrl == selph.settings.ctrl_to_zoom {
+                        scroll = Vec2::splat(scroll.x + scroll.y);
+                        let mut zoom_factor = Vec2::from([
+                            (scroll.x * selph.settings.zoom_speed / 10.0).exp(),
+                            (scroll.y * selph.settings.zoom_speed / 10.0).exp(),
+                        ]);
+                        if selph.settings.lock_x {
+                            zoom_factor.x = 1.0;
+                        }
+                        if selph.settings.lock_y {
+                            zoom_factor.y = 1.0;
+                        }
+                        plot_ui.zoom_bounds_around_hovered(zoom_factor);
+                    } else {
+                        if modifiers.shift == selph.settings.shift_to_horizontal {
+                            scroll = Vec2::new(scroll.y, scroll.x);
+                        }
+                        if selph.settings.lock_x {
+                            scroll.x = 0.0;
+                        }
+                        if selph.settings.lock_y {
+                            scroll.y = 0.0;
+                        }
+                        let delta_pos = selph.settings.scroll_speed * scroll;
+                        plot_ui.translate_bounds(delta_pos);
+                    }
+                }
+                if let Some(dragged_point) = selph.dragged_point {
+                    // Left alone until egui sees a drag, so a click to select doesn't nudge the point
+                    if let (true, Some(pointer_pos)) = (plot_ui.response().dragged(), pointer_coordinate) {
+                        let point = &mut selph.session.points[dragged_point];
+                        point.x = pointer_pos.x as f32 + selph.drag_offset[0];
+                        point.y = pointer_pos.y as f32 + selph.drag_offset[1];
+                    }
+                } else if plot_hovered && pointer_down {
+                    let mut pointer_translate = -plot_ui.pointer_coordinate_drag_delta();
+                    if selph.settings.lock_x {
+                        pointer_translate.x = 0.0;
+                    }
+                    if selph.settings.lock_y {
+                        pointer_translate.y = 0.0;
+                    }
+                    plot_ui.translate_bounds(pointer_translate);
+                }
+
+                if let (Some(floor_plan), Some(texture)) = (&selph.session.floor_plan, &selph.floor_plan_texture) {
+                    let [center_x, center_y] = floor_plan.center(selph.session.unit);
+
+                    plot_ui.image(PlotImage::new(texture.id(), PlotPoint::new(center_x, center_y), floor_plan.size(selph.session.unit)).allow_hover(false));
+                }
+
+                let mut points_vec = vec![];
+
+                selph.session.points.iter().clone().for_each(|point| { // COMBINE THIS WITH THE HOVER DETECTION FOR BETTER EFFICIENCY
+                    points_vec.push([f64::from(point.x), f64::from(point.y)]);
+                });
+
+                let triangle_bounds = Polygon::new(PlotPoints::from(points_vec.clone())).allow_hover(false).fill_color(Color32::from_rgba_unmultiplied(255, 255, 255, 20)).stroke(Stroke::new(1.0, Color32::WHITE)).allow_hover(true);
+
+                plot_ui.polygon(triangle_bounds);
+
+                plot_walls(plot_ui, &selph.session.walls);
+                plot_ranges(plot_ui, range_session(selph));
+
+                if selph.drawing_walls {
+                    if let (Some(pointer_pos), true) = (pointer_coordinate, pointer_clicked && plot_hovered) {
+                        match selph.wall_start.take() {
+                            None => selph.wall_start = Some([pointer_pos.x, pointer_pos.y]),
+                            Some([x, y]) => {
+                                let wall = Wall {
+                                    start: [x as f32, y as f32],
+                                    end: [pointer_pos.x as f32, pointer_pos.y as f32],
+                                    attenuation_db: selph.settings.wall_attenuation,
+                                };
+                                let index = selph.session.walls.len();
+                                selph.history.apply(&mut selph.session, Edit::AddWall { index, wall });
+                            }
+                        }
+                    }
+
+                    if let Some([x, y]) = selph.wall_start {
+                        plot_point(plot_ui, x as f32, y as f32);
+                    }
+                }
+
+                if let Some(collector) = &selph.collector {
+                    let sensors: Vec<[f64; 2]> = collector.get_sensors().iter().map(|(_, position, _, _)| [f64::from(position.x), f64::from(position.y)]).collect();
+                    plot_ui.points(Points::new(sensors).shape(MarkerShape::Square).radius(4.0).color(Color32::LIGHT_BLUE).name("Sensors"));
+                }
+
+                if let Some(calibration) = selph.calibration.as_mut() {
+                    if let Some(pointer_pos) = plot_ui.pointer_coordinate() {
+                        if pointer_clicked && plot_ui.response().hovered() {
+                            calibration.add_point(pointer_pos.x, pointer_pos.y);
+                        }
+                    }
+
+                    for [x, y] in calibration.points.iter() {
+                        plot_point(plot_ui, *x as f32, *y as f32);
+                    }
+
+                    if calibration.points.len() == 2 {
+                        plot_ui.line(Line::new(PlotPoints::from(calibration.points.clone())).color(Color32::YELLOW));
+                    }
+                }
+
+                // HOVER DETECTION CODE
+                if let (Some(pointer_pos), None, false) = (pointer_coordinate, &selph.calibration, selph.drawing_walls) {
+                    if let Some(tooltip) = range_tooltip(range_session(selph), pointer_pos, plot_ui.transform()) {
+                        let ctx = plot_ui.ctx().clone();
+                        let response = plot_ui.response();
+
+                        egui::show_tooltip_at_pointer(&ctx, response.layer_id, response.id.with("range_tooltip"), |ui| ui.label(tooltip));
+                    }
+
+                    if let Some(index) = hovered {
+                        // CLICKING WORKS HORRAY
+                        if pointer_clicked {
+                            if selph.selected_point.is_some() && selph.selected_point == Some(index) {
+                                selph.selected_point = None;
+                            } else {
+                                selph.selected_point = Some(index);
+                            }
+                        }
+                    } else if pointer_clicked && plot_hovered {
+                        // Clicking empty space adds a point there
+                        let index = selph.session.points.len();
+                        selph.history.apply(&mut selph.session, Edit::AddPoint { index, point: Point::new(pointer_pos.x as f32, pointer_pos.y as f32, None) });
+                        selph.selected_point = Some(index);
+                    }
+
+                    if secondary_clicked && plot_hovered {
+                        selph.context_point = hovered;
+                        selph.context_position = [pointer_pos.x, pointer_pos.y];
+                    }
+                }
+
+                if let Some(selected_point) = selph.selected_point {
+                    let selected_point = selph.session.points[selected_point].clone();
+
+                    plot_point(plot_ui, selected_point.x, selected_point.y);
+                }
+
+                if selph.particles {
+                    plot_particles(plot_ui, &selph.particle_filter);
+                }
+
+                if selph.tracking {
+                    plot_track(plot_ui, &selph.tracker);
+                } else if let (Some([x, y]), false) = (estimate, selph.particles) {
+                    plot_point(plot_ui, x, y);
+                }
+
+                if selph.show_sweep {
+                    plot_sweep(plot_ui, &selph.sweep);
+                }
+            });
+
+            plot_response.response.context_menu(|ui| {
+                if let Some(index) = selph.context_point {
+                    if ui.button("Delete Point").clicked() {
+                        delete_point(selph, index);
+                        ui.close_menu();
+                    }
+                } else if ui.button("Add Point Here").clicked() {
+                    let [x, y] = selph.context_position;
+                    let index = selph.session.points.len();
+                    selph.history.apply(&mut selph.session, Edit::AddPoint { index, point: Point::new(x as f32, y as f32, None) });
+                    ui.close_menu();
+                }
+            });
+        });
+}
+
+// Loads settings into the running app, for a fresh start or after resetting them
+fn apply_settings(selph: &mut TriangleGator, settings: Settings) {
+    // Left alone once readings are taken, so resetting the settings never changes a survey in progress
+    if selph.session.points.iter().all(|point| point.net_info.is_none()) {
+        selph.session.set_unit(settings.unit);
+        selph.session.path_loss_exponent = settings.path_loss_exponent;
+    }
+
+    if settings.interface.as_deref() != selph.network_manager.get_interface() {
+        selph.network_manager.set_interface(settings.interface.clone());
+        selph.rssi_monitor.interface = settings.interface.clone();
+    }
+
+    selph.settings = settings;
+}
+
+fn settings_ui(ui: &mut egui::Ui, selph: &mut TriangleGator) {
+    let settings = ui.collapsing("Settings", |ui| {
+        egui::Grid::new("settings").num_columns(2).show(ui, |ui| {
+            ui.label("Sample Scale");
+            ui.add(DragValue::new(&mut selph.settings.sample_scale).speed(1).range(RangeInclusive::new(1, 20)));
+            ui.end_row();
+
+            ui.label("Sample Length");
+            ui.add(DragValue::new(&mut selph.settings.sample_length).speed(1).range(RangeInclusive::new(1, 2000)).suffix(" ms"));
+            ui.end_row();
+
+            ui.label("Zoom Speed");
+            ui.add(DragValue::new(&mut selph.settings.zoom_speed).speed(0.05).range(RangeInclusive::new(0.1, 10.0)));
+            ui.end_row();
+
+            ui.label("Scroll Speed");
+            ui.add(DragValue::new(&mut selph.settings.scroll_speed).speed(0.05).range(RangeInclusive::new(0.1, 10.0)));
+            ui.end_row();
+
+            ui.label("Lock Axes");
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut selph.settings.lock_x, "x");
+                ui.checkbox(&mut selph.settings.lock_y, "y");
+            });
+            ui.end_row();
+        });
+
+        ui.checkbox(&mut selph.settings.ctrl_to_zoom, "Hold Ctrl to zoom").on_hover_text("Otherwise the wheel zooms, and scrolls with Ctrl held");
+        ui.checkbox(&mut selph.settings.shift_to_horizontal, "Hold Shift to scroll sideways");
+
+        if ui.button("Reset to Defaults").clicked() {
+            let settings = Settings { path_loss_exponent: selph.session.path_loss_exponent, unit: selph.session.unit, ..Settings::default() };
+            apply_settings(selph, settings);
+        }
+    });
+
+    settings.header_response.on_hover_text("Kept between launches. Undo only covers the survey, so it leaves these alone.");
+}
+
+// FUNCTIONS TO CHECK SEC OF NETWORK, CONNECT / LOGIN, AND THEN PING THE NETWORK TO GET THE SELECTED NETINFO
+
+// Looks for the backend tools and wireless adapters again, raising or clearing their banners
+fn check_backend(selph: &mut TriangleGator) {
+    let missing_tools = network_manager::missing_tools();
+
+    for tool in network_manager::BACKEND_TOOLS {
+        if missing_tools.contains(&tool) {
+            selph.notifications.raise(Problem::MissingTool(tool));
+        } else {
+            selph.notifications.resolve(&Problem::MissingTool(tool));
+        }
+    }
+
+    selph.interfaces = interfaces::wireless_interfaces();
+
+    if selph.interfaces.is_empty() {
+        selph.notifications.raise(Problem::NoInterface);
+    } else {
+        selph.notifications.resolve(&Problem::NoInterface);
+    }
+}
+
+fn scan_networks(selph: &mut TriangleGator) {
+    selph.last_scan = Some(Instant::now());
+
+    match selph.network_manager.scan_networks() {
+        Ok(()) => {
+            selph.notifications.resolve(&Problem::MissingTool("nmcli"));
+            selph.notifications.resolve(&Problem::ScanFailed(String::new()));
+        }
+        Err(BackendError::MissingTool(tool)) => selph.notifications.raise(Problem::MissingTool(tool)),
+        Err(e) => selph.notifications.raise(Problem::ScanFailed(e.to_string())),
+    }
+}
+
+// Problems that stop the survey, each with what to do about it
+fn banners_ui(ui: &mut egui::Ui, selph: &mut TriangleGator) {
+    let mut check_again = false;
+    let mut dismissed = None;
+
+    for problem in selph.notifications.problems() {
+        egui::Frame::NONE
+            .fill(Color32::from_rgb(90, 30, 30))
+            .inner_margin(egui::Margin::same(6))
+            .corner_radius(4.0)
+            .show(ui, |ui| {
+                ui.set_width(ui.available_width());
+                ui.horizontal_wrapped(|ui| {
+                    ui.colored_label(Color32::WHITE, problem.message());
+
+                    match problem {
+                        Problem::MissingTool(_) | Problem::NoInterface | Problem::ScanFailed(_) => {
+                            if ui.small_button("Check Again").clicked() {
+                                check_again = true;
+                            }
+                        }
+                        Problem::SingularGeometry => {
+                            if ui.small_button("Dismiss").clicked() {
+                                dismissed = Some(problem.clone());
+                            }
+                        }
+                    }
+                });
+            });
+    }
+
+    if check_again {
+        check_backend(selph);
+        scan_networks(selph);
+    }
+
+    if let Some(problem) = dismissed {
+        selph.notifications.resolve(&problem);
+    }
+}
+
+// Passing messages stacked in the bottom right corner, clicked to dismiss
+fn toasts_ui(ctx: &egui::Context, selph: &mut TriangleGator) {
+    let mut dismissed = None;
+    let toasts = selph.notifications.toasts();
+
+    if let Some(next_expiry) = toasts.iter().map(|toast| toast.expires()).min() {
+        ctx.request_repaint_after(next_expiry.saturating_duration_since(Instant::now()));
+    }
+
+    egui::Area::new(egui::Id::new("toasts"))
+        .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-16.0, -16.0))
+        .order(egui::Order::Foreground)
+        .show(ctx, |ui| {
+            ui.set_max_width(240.0);
+
+            for (index, toast) in toasts.iter().enumerate() {
+                let fill = match toast.severity {
+                    Severity::Success => Color32::from_rgb(30, 80, 40),
+                    Severity::Info => Color32::from_rgb(40, 50, 80),
+                    Severity::Error => Color32::from_rgb(100, 30, 30),
+                };
+
+                let response = egui::Frame::NONE
+                    .fill(fill)
+                    .inner_margin(egui::Margin::same(8))
+                    .corner_radius(4.0)
+                    .show(ui, |ui| {
+                        ui.colored_label(Color32::WHITE, &toast.message);
+                    })
+                    .response
+                    .interact(egui::Sense::click());
+
+                if response.clicked() {
+                    dismissed = Some(index);
+                }
+            }
+        });
+
+    if let Some(index) = dismissed {
+        selph.notifications.dismiss(index);
+    }
+}
+
+// MAYBE ALSO A LOADING KINDA SWIRL OR BAR THING, THAT DISPLAYS WHILE TESTING A POINT, ONCE EVERY quarter SECOND, LIKE 5 TIMES
+// Im thinking, little bar graph, also disable reset_calc when bar graph is testing
+
+// Session the range circles are drawn from: the live readings while testing, otherwise the last calculation
+fn range_session(selph: &TriangleGator) -> &Session {
+    if selph.session.points.iter().any(|point| point.net_info.is_some()) {
+        return &selph.session;
+    }
+
+    return selph.solved_session.as_ref().unwrap_or(&selph.session);
+}
+
+// Draws each tested point's range as a circle, and a residual line from the estimate to it
+fn plot_ranges(plot_ui: &mut PlotUi, session: &Session) {
+    let calc = session.calculator();
+
+    for point in session.points.iter() {
+        let Some(range) = calc.estimate_range(point) else {
+            continue;
+        };
+
+        let residual = session.location.as_ref().and_then(|location| calc.get_residual(point, location));
+        let color = if point.excluded { Color32::DARK_GRAY } else { residual_color(residual, range) };
+
+        let circle: Vec<[f64; 2]> = (0..=RANGE_SEGMENTS).map(|i| {
+            let angle = std::f64::consts::TAU * i as f64 / RANGE_SEGMENTS as f64;
+            [f64::from(point.x) + f64::from(range) * angle.cos(), f64::from(point.y) + f64::from(range) * angle.sin()]
+        }).collect();
+
+        plot_ui.line(Line::new(PlotPoints::from(circle)).color(color).allow_hover(false));
+
+        if let (Some(location), false) = (&session.location, point.excluded) {
+            let (dx, dy) = (location.x - point.x, location.y - point.y);
+            let length = (dx.powi(2) + dy.powi(2)).sqrt().max(f32::EPSILON);
+
+            // Closest point on the circle to the estimate
+            let on_circle = [f64::from(point.x + dx / length * range), f64::from(point.y + dy / length * range)];
+
+            let residual_line = vec![[f64::from(location.x), f64::from(location.y)], on_circle];
+            plot_ui.line(Line::new(PlotPoints::from(residual_line)).color(color).style(LineStyle::dashed_dense()).allow_hover(false));
+        }
+    }
+}
+
+// Green when the estimate sits on the circle, through yellow to red the further off it is
+fn residual_color(residual: Option<f32>, range: f32) -> Color32 {
+    return match residual.map(|residual| (residual / range.max(f32::EPSILON)).abs()) {
+        None => Color32::LIGHT_BLUE,
+        Some(error) if error < 0.1 => Color32::GREEN,
+        Some(error) if error < 0.25 => Color32::YELLOW,
+        Some(_) => Color32::RED,
+    };
+}
+
+// Distance, RSSI and residual of the tested point whose marker or range circle is under the pointer
+fn range_tooltip(session: &Session, pointer_pos: PlotPoint, transform: &PlotTransform) -> Option<String> {
+    let calc = session.calculator();
+    let hover_threshold = 8.0;
+
+    for point in session.points.iter().rev() {
+        let Some(range) = calc.estimate_range(point) else {
+            continue;
+        };
+
+        let from_center = ((pointer_pos.x - f64::from(point.x)).powi(2) + (pointer_pos.y - f64::from(point.y)).powi(2)).sqrt();
+        let on_circle = (from_center - f64::from(range)).abs() * transform.dpos_dvalue_x().abs() < hover_threshold;
+
+        if !on_circle && !point_is_hovered(point, pointer_pos, transform) {
+            continue;
+        }
+
+        let unit = session.unit.suffix();
+        let net_info = point.net_info.as_ref().unwrap();
+        let mut tooltip = format!("RSSI: {:.2} dBm\nDistance: {:.2} {}", net_info.measured_power.unwrap(), range, unit);
+
+        if let Some(residual) = session.location.as_ref().and_then(|location| calc.get_residual(point, location)) {
+            tooltip.push_str(&format!("\nResidual: {:+.2} {}", residual, unit));
+        }
+
+        return Some(tooltip);
+    }
+
+    return None;
+}
+
+// Measured on screen, so points stay easy to grab however far the plot is zoomed or calibrated
+fn point_is_hovered(point: &Point, pointer_pos: PlotPoint, transform: &PlotTransform) -> bool {
+    let hover_threshold = 8.0;
+
+    let point_pos = transform.position_from_point(&PlotPoint::new(point.x, point.y));
+    let pointer_pos = transform.position_from_point(&pointer_pos);
+
+    return point_pos.distance(pointer_pos) < hover_threshold;
+}
+
+// Index of the hovered point, the last drawn one winning if they overlap
+fn hovered_point(points: &[Point], pointer_pos: PlotPoint, transform: &PlotTransform) -> Option<usize> {
+    return points.iter().rposition(|point| point_is_hovered(point, pointer_pos, transform));
+}
+
+fn delete_point(selph: &mut TriangleGator, index: usize) {
+    let point = selph.session.points[index].clone();
+    selph.history.apply(&mut selph.session, Edit::DeletePoint { index, point });
+
+    selph.selected_point = match selph.selected_point {
+        Some(selected) if selected == index => None,
+        Some(selected) if selected > index => Some(selected - 1),
+        selected => selected,
+    };
+    selph.dragged_point = None;
+}
+
+fn point_inspector_ui(ui: &mut egui::Ui, point: &mut Point, unit: DistanceUnit, origin: Option<&GeoOrigin>) {
+    let suffix = format!(" {}", unit.suffix());
+
+    ui.horizontal(|ui| {
+        ui.label("X");
+        ui.add(DragValue::new(&mut point.x).speed(0.1).suffix(&suffix));
+        ui.label("Y");
+        ui.add(DragValue::new(&mut point.y).speed(0.1).suffix(&suffix));
+        ui.label("Z");
+        ui.add(DragValue::new(&mut point.z).speed(0.1).suffix(&suffix));
+    });
+
+    // Georeferenced points can be typed in as latitude/longitude too, the ENU frame being in metres
+    if let Some(origin) = origin {
+        let (mut latitude, mut longitude) = origin.to_geodetic(f64::from(unit.to_metres(point.x)), f64::from(unit.to_metres(point.y)));
+
+        ui.horizontal(|ui| {
+            ui.label("Lat");
+            let lat_changed = ui.add(DragValue::new(&mut latitude).speed(0.000_01).fixed_decimals(7)).changed();
+            ui.label("Lon");
+            let lon_changed = ui.add(DragValue::new(&mut longitude).speed(0.000_01).fixed_decimals(7)).changed();
+
+            if lat_changed || lon_changed {
+                let (east, north) = origin.to_local(latitude, longitude);
+                point.x = unit.from_metres(east as f32);
+                point.y = unit.from_metres(north as f32);
+            }
+        });
+    }
+}
+
+fn georeference_ui(ui: &mut egui::Ui, selph: &mut TriangleGator) {
+    let origin_before = selph.session.origin.clone();
+
+    ui.collapsing("Georeference", |ui| {
+        let mut georeferenced = selph.session.origin.is_some();
+
+        ui.horizontal(|ui| {
+            if ui.checkbox(&mut georeferenced, "Georeferenced").changed() {
+                selph.session.origin = if georeferenced { Some(GeoOrigin::new(0.0, 0.0)) } else { None };
+            }
+
+            ui.add(TextEdit::singleline(&mut selph.settings.gpsd_addr).desired_width(110.0).hint_text("gpsd address"));
+        });
+
+        if let Some(origin) = selph.session.origin.as_mut() {
+            ui.horizontal(|ui| {
+                ui.label("Origin");
+                ui.add(DragValue::new(&mut origin.latitude).speed(0.000_01).fixed_decimals(7).range(RangeInclusive::new(-90.0, 90.0)));
+                ui.add(DragValue::new(&mut origin.longitude).speed(0.000_01).fixed_decimals(7).range(RangeInclusive::new(-180.0, 180.0)));
+            });
+        }
+
+        ui.horizontal(|ui| {
+            if ui.button("Origin From GPS").clicked() {
+                match gpsd::read_fix(&selph.settings.gpsd_addr, GPS_TIMEOUT) {
+                    Ok(fix) => selph.session.origin = Some(GeoOrigin { latitude: fix.latitude, longitude: fix.longitude, altitude: fix.altitude.unwrap_or(0.0) }),
+                    Err(e) => selph.notifications.error(format!("No GPS fix from gpsd at {}: {}. Check gpsd is running and has a fix.", selph.settings.gpsd_addr, e)),
+                }
+            }
+
+            let can_place = selph.selected_point.is_some() && selph.session.is_georeferenced();
+
+            if ui.add_enabled(can_place, Button::new("Point From GPS")).clicked() {
+                match gpsd::read_fix(&selph.settings.gpsd_addr, GPS_TIMEOUT) {
+                    Ok(fix) => {
+                        let position = selph.session.point_at(fix.latitude, fix.longitude).unwrap();
+                        let index = selph.selected_point.unwrap();
+                        let from = history::position(&selph.session.points[index]);
+                        selph.history.apply(&mut selph.session, Edit::MovePoint { index, from, to: [position.x, position.y, from[2]] });
+                    }
+                    Err(e) => selph.notifications.error(format!("No GPS fix from gpsd at {}: {}. Check gpsd is running and has a fix.", selph.settings.gpsd_addr, e)),
+                }
+            }
+        });
+
+        if let Some((latitude, longitude)) = selph.session.location_geodetic() {
+            ui.label(format!("Estimate: {:.7}, {:.7}", latitude, longitude));
+        }
+    });
+
+    if selph.session.origin != origin_before {
+        selph.history.record(Edit::SetOrigin { from: origin_before, to: selph.session.origin.clone() });
+    }
+}
+
+fn plot_point(plot_ui: &mut PlotUi, x: f32, y: f32) {
+    let point_x = f64::from(x);
+    let point_y = f64::from(y);
+
+    let points_vec = vec![
+        [point_x - 3.0, point_y],
+        [point_x, point_y + 3.0],
+        [point_x + 3.0, point_y],
+        [point_x, point_y- 3.0],
+    ];
+
+    let point_bounds = Polygon::new(PlotPoints::from(points_vec)).allow_hover(false).fill_color(Color32::from_rgba_unmultiplied(255, 0, 0, 80)).stroke(Stroke::new(2.0, Color32::RED));
+
+    plot_ui.polygon(point_bounds);
+}
+
+fn units_ui(ui: &mut egui::Ui, selph: &mut TriangleGator) {
+    let mut unit = selph.session.unit;
+
+    ui.horizontal(|ui| {
+        ui.label("Units");
+
+        egui::ComboBox::from_id_salt("units").selected_text(unit.to_string()).show_ui(ui, |ui| {
+            for option in DistanceUnit::ALL {
+                ui.selectable_value(&mut unit, option, option.to_string());
+            }
+        });
+    });
+
+    if unit == selph.session.unit {
+        return;
+    }
+
+    // Convert everything so nothing moves, the filters' state is in the old unit so start them over
+    let before = selph.session.clone();
+    selph.session.set_unit(unit);
+    selph.history.record(Edit::Replace { description: "change units", before: Box::new(before), after: Box::new(selph.session.clone()) });
+    if let Some(solved_session) = selph.solved_session.as_mut() {
+        solved_session.set_unit(unit);
+    }
+
+    selph.tracker.reset();
+    selph.particle_filter.reset();
+    selph.calibration = None;
+    selph.wall_start = None;
+}
+
+fn floor_plan_ui(ui: &mut egui::Ui, selph: &mut TriangleGator) {
+    ui.collapsing("Floor Plan", |ui| {
+        ui.horizontal(|ui| {
+            ui.add(TextEdit::singleline(&mut selph.floor_plan_path).desired_width(140.0).hint_text("floor plan .png / .jpg"));
+
+            if ui.button("Load").clicked() {
+                match load_floor_plan(ui.ctx(), &selph.floor_plan_path, selph.session.unit) {
+                    Ok((floor_plan, texture)) => {
+                        selph.session.floor_plan = Some(floor_plan);
+                        selph.floor_plan_texture = Some(texture);
+                    }
+                    Err(e) => selph.notifications.error(format!("Couldn't load floor plan {}: {}. It needs to be a PNG or JPEG.", selph.floor_plan_path, e)),
+                }
+            }
+        });
+
+        if selph.session.floor_plan.is_none() {
+            return;
+        }
+
+        if let Some(calibration) = selph.calibration.as_mut() {
+            ui.label(format!("Click two points on the plan ({}/2)", calibration.points.len()));
+
+            let mut scale_factor = None;
+            let mut cancelled = false;
+
+            ui.horizontal(|ui| {
+                ui.label("Distance");
+                ui.add(DragValue::new(&mut calibration.distance).speed(0.1).range(RangeInclusive::new(0.01, 10000.0)).suffix(format!(" {}", selph.session.unit.suffix())));
+
+                if ui.add_enabled(calibration.is_complete(), Button::new("Apply")).clicked() {
+                    scale_factor = calibration.scale_factor();
+                }
+
+                cancelled = ui.button("Cancel").clicked();
+            });
+
+            if let Some(scale_factor) = scale_factor {
+                let before = selph.session.clone();
+                selph.session.calibrate(scale_factor);
+                selph.history.record(Edit::Replace { description: "calibrate scale", before: Box::new(before), after: Box::new(selph.session.clone()) });
+
+                // Keep the exportable copy on the same scale as the plot
+                if let Some(solved_session) = selph.solved_session.as_mut() {
+                    solved_session.calibrate(scale_factor);
+                }
+            }
+
+            if scale_factor.is_some() || cancelled {
+                selph.calibration = None;
+            }
+        } else if ui.button("Calibrate Scale").clicked() {
+            selph.calibration = Some(Calibration::default());
+            selph.selected_point = None;
+        }
+    });
+}
+
+// The interface being scanned and measured on, as the backends would pick it if none was chosen
+fn current_interface(selph: &TriangleGator) -> Option<&WirelessInterface> {
+    return match selph.network_manager.get_interface() {
+        Some(name) => selph.interfaces.iter().find(|interface| interface.name == name),
+        None => selph.interfaces.first(),
+    };
+}
+
+fn interface_ui(ui: &mut egui::Ui, selph: &mut TriangleGator) {
+    let mut selected = selph.network_manager.get_interface().map(String::from);
+
+    ui.horizontal(|ui| {
+        let selected_text = selected.clone().unwrap_or(String::from("Default"));
+
+        egui::ComboBox::from_id_salt("interface").selected_text(selected_text).width(140.0).show_ui(ui, |ui| {
+            ui.selectable_value(&mut selected, None, "Default");
+
+            for interface in selph.interfaces.iter() {
+                ui.selectable_value(&mut selected, Some(interface.name.clone()), interface.describe());
+            }
+        });
+
+        if ui.button("Refresh").clicked() {
+            check_backend(selph);
+        }
+    });
+
+    if let Some(interface) = current_interface(selph) {
+        let capabilities = if interface.capabilities.is_empty() { String::from("capabilities unknown") } else { interface.capabilities.join(", ") };
+        ui.label(format!("{}\n{}", interface.describe(), capabilities));
+    } else {
+        ui.label("No wireless interface found");
+    }
+
+    if selected.as_deref() == selph.network_manager.get_interface() {
+        return;
+    }
+
+    selph.network_manager.set_interface(selected.clone());
+    selph.network_manager.clear_available_networks();
+
+    // The monitor only picks up its interface when started
+    selph.rssi_monitor.interface = selected;
+    if selph.rssi_monitor.is_running() {
+        selph.rssi_monitor.stop();
+        selph.rssi_monitor.start();
+    }
+}
+
+// Entry for a network that doesn't broadcast its SSID, returns it once added
+fn hidden_network_ui(ui: &mut egui::Ui, selph: &mut TriangleGator) -> Option<Network> {
+    let mut added = None;
+
+    ui.collapsing("Hidden Network", |ui| {
+        ui.horizontal(|ui| {
+            ui.add(TextEdit::singleline(&mut selph.hidden_ssid).desired_width(100.0).hint_text("SSID"));
+
+            egui::ComboBox::from_id_salt("hidden_kind").selected_text(selph.hidden_kind.to_string()).show_ui(ui, |ui| {
+                for kind in SecurityKind::ALL {
+                    ui.selectable_value(&mut selph.hidden_kind, kind, kind.to_string());
+                }
+            });
+        });
+
+        if ui.add_enabled(!selph.hidden_ssid.trim().is_empty(), Button::new("Add")).clicked() {
+            added = Some(Network::hidden(selph.hidden_ssid.trim().to_string(), selph.hidden_kind));
+        }
+    });
+
+    return added;
+}
+
+// The fields the network's security kind needs filled in before connecting
+fn credentials_ui(ui: &mut egui::Ui, selph: &mut TriangleGator, kind: SecurityKind) {
+    if kind == SecurityKind::Enterprise {
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_salt("eap").selected_text(selph.enterprise.eap.to_string()).width(60.0).show_ui(ui, |ui| {
+                for eap in EapMethod::ALL {
+                    ui.selectable_value(&mut selph.enterprise.eap, eap, eap.to_string());
+                }
+            });
+
+            egui::ComboBox::from_id_salt("phase2").selected_text(selph.enterprise.phase2.to_string()).width(80.0).show_ui(ui, |ui| {
+                for phase2 in Phase2Auth::ALL {
+                    ui.selectable_value(&mut selph.enterprise.phase2, phase2, phase2.to_string());
+                }
+            });
+        });
+
+        ui.add(TextEdit::singleline(&mut selph.enterprise.identity).desired_width(160.0).hint_text("identity"));
+        ui.add(TextEdit::singleline(&mut selph.enterprise.anonymous_identity).desired_width(160.0).hint_text("anonymous identity (optional)"));
+        ui.add(TextEdit::singleline(&mut selph.ca_cert_path).desired_width(160.0).hint_text("CA certificate (optional)"));
+        ui.add(TextEdit::singleline(&mut *selph.enterprise.password).password(true).desired_width(160.0).hint_text("password"));
+
+        if selph.ca_cert_path.trim().is_empty() {
+            ui.colored_label(Color32::YELLOW, "Without a CA certificate the server isn't verified");
+        }
+    } else if kind.needs_password() {
+        ui.add(TextEdit::singleline(&mut *selph.network_password).password(true).desired_width(100.0).hint_text("password"));
+    }
+
+    if kind.needs_password() {
+        ui.checkbox(&mut selph.settings.remember_password, "Remember");
+    }
+}
+
+fn credentials(selph: &TriangleGator, kind: SecurityKind) -> Credentials {
+    if kind == SecurityKind::Enterprise {
+        let ca_cert = selph.ca_cert_path.trim();
+
+        return Credentials::Enterprise(EnterpriseCredentials {
+            ca_cert: if ca_cert.is_empty() { None } else { Some(PathBuf::from(ca_cert)) },
+            ..selph.enterprise.clone()
+        });
+    }
+
+    if kind.needs_password() {
+        return Credentials::Password(selph.network_password.clone());
+    }
+
+    return Credentials::None;
+}
+
+fn device_profile_ui(ui: &mut egui::Ui, selph: &mut TriangleGator) {
+    ui.collapsing("Device Profile", |ui| {
+        let Some(interface) = current_interface(selph).cloned() else {
+            ui.label("No wireless interface found");
+            return;
+        };
+
+        ui.label(interface.describe());
+
+        let Some(applied) = selph.network_manager.get_device_profile().cloned() else {
+            if ui.button("Create Profile").clicked() {
+                selph.network_manager.set_device_profile(Some(DeviceProfile::new(&interface.name, &interface)));
+            }
+            return;
+        };
+
+        // Start over from the applied profile when the adapter changes
+        let mut profile = match selph.profile_draft.take() {
+            Some(draft) if draft.mac == applied.mac && draft.driver == applied.driver => draft,
+            _ => applied.clone(),
+        };
+
+        ui.horizontal(|ui| {
+            ui.add(TextEdit::singleline(&mut profile.name).desired_width(80.0).hint_text("name"));
+            ui.label("Offset");
+            ui.add(DragValue::new(&mut profile.rssi_offset_db).speed(0.1).range(RangeInclusive::new(-40.0, 40.0)).suffix(" dB"));
+            ui.label("Gain");
+            ui.add(DragValue::new(&mut profile.rx_gain_dbi).speed(0.1).range(RangeInclusive::new(-10.0, 30.0)).suffix(" dBi"));
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Reference");
+            ui.add(DragValue::new(&mut selph.reference_rssi).speed(0.1).range(RangeInclusive::new(-120.0, 0.0)).suffix(" dBm"));
+
+            if ui.button("Calibrate").clicked() {
+                let readings = network_manager::sample_link(selph.network_manager.get_interface(), selph.settings.sample_scale, selph.settings.sample_length);
+
+                match profile.calibrate(&readings, selph.reference_rssi) {
+                    Some(offset) => {
+                        selph.network_manager.set_device_profile(Some(profile.clone()));
+                        selph.notifications.success(format!("Calibrated {} against the reference: {:+.2} dB", profile.name, offset));
+                    }
+                    None => selph.notifications.error(String::from("Couldn't read the signal level of the current link. Connect to the reference network first.")),
+                }
+            }
+
+            if ui.button("Save").clicked() {
+                selph.network_manager.set_device_profile(Some(profile.clone()));
+
+                let saved = DeviceProfiles::load().and_then(|mut profiles| {
+                    profiles.upsert(profile.clone());
+                    profiles.save()
+                });
+
+                if let Err(e) = saved {
+                    selph.notifications.error(format!("Couldn't save the device profile: {}", e));
+                }
+            }
+        });
+
+        if selph.network_manager.get_device_profile() != Some(&profile) {
+            ui.label("Unsaved changes, readings use the profile as last calibrated or saved");
+        }
+
+        selph.profile_draft = Some(profile);
+    });
+}
+
+fn rssi_monitor_ui(ui: &mut egui::Ui, selph: &mut TriangleGator) {
+    ui.collapsing("Live RSSI", |ui| {
+        ui.horizontal(|ui| {
+            if selph.rssi_monitor.is_running() {
+                if ui.button("Stop").clicked() {
+                    selph.rssi_monitor.stop();
+                }
+            } else if ui.button("Start").clicked() {
+                selph.rssi_monitor.start();
+            }
+
+            let mut rate_ms = selph.rssi_monitor.get_rate_ms();
+            if ui.add(DragValue::new(&mut rate_ms).speed(10).range(RangeInclusive::new(50, 5000)).suffix(" ms")).changed() {
+                selph.rssi_monitor.set_rate_ms(rate_ms);
+            }
+
+            ui.add(DragValue::new(&mut selph.rssi_monitor.window_secs).speed(1).range(RangeInclusive::new(1.0, 120.0)).suffix(" s"));
+        });
+
+        let window = selph.rssi_monitor.get_window();
+        let rssi: Vec<[f64; 2]> = window.iter().map(|(secs, reading)| [*secs, f64::from(reading.signal_level)]).collect();
+        let tx_power: Vec<[f64; 2]> = window.iter().map(|(secs, reading)| [*secs, f64::from(reading.tx_power)]).collect();
+
+        Plot::new("rssi_monitor")
+            .legend(Legend::default())
+            .height(100.0)
+            .allow_zoom(false)
+            .allow_drag(false)
+            .allow_scroll(false)
+            .show(ui, |plot_ui| {
+                plot_ui.line(Line::new(PlotPoints::from(rssi)).name("RSSI").color(Color32::GREEN));
+                plot_ui.line(Line::new(PlotPoints::from(tx_power)).name("TX Power").color(Color32::LIGHT_BLUE));
+            });
+
+        ui.horizontal(|ui| {
+            match (selph.rssi_monitor.mean(), selph.rssi_monitor.variance()) {
+                (Some(mean), Some(variance)) => ui.label(format!("Mean: {:.2} dBm  Var: {:.2}", mean, variance)),
+                _ => ui.label("No readings"),
+            };
+
+            let can_capture = selph.selected_point.is_some() && !selph.rssi_monitor.get_window().is_empty();
+
+            if ui.add_enabled(can_capture, Button::new("Capture Now")).clicked() {
+                let net_info = selph.rssi_monitor.capture();
+                measure_point(selph, selph.selected_point.unwrap(), net_info);
+                selph.selected_point = None;
+            }
+        });
+    });
+}
+
+fn log_ui(ui: &mut egui::Ui, selph: &mut TriangleGator) {
+    ui.collapsing("Log", |ui| {
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_salt("log_level").selected_text(selph.log_level.as_str()).width(60.0).show_ui(ui, |ui| {
+                for level in [Level::ERROR, Level::WARN, Level::INFO, Level::DEBUG, Level::TRACE] {
+                    ui.selectable_value(&mut selph.log_level, level, level.as_str());
+                }
+            });
+
+            ui.add(TextEdit::singleline(&mut selph.log_filter).desired_width(80.0).hint_text("filter"));
+        });
+
+        // Level ordering in tracing runs from TRACE (least) up to ERROR, the reverse of severity
+        let filter = selph.log_filter.to_lowercase();
+        let records: Vec<String> = selph.logging.buffer.records().iter()
+            .filter(|record| record.level <= selph.log_level)
+            .map(|record| record.to_string())
+            .filter(|line| filter.is_empty() || line.to_lowercase().contains(&filter))
+            .collect();
+
+        egui::ScrollArea::vertical().max_height(120.0).stick_to_bottom(true).show(ui, |ui| {
+            for line in records.iter() {
+                ui.label(egui::RichText::new(line).monospace().size(10.0));
+            }
+        });
+
+        ui.horizontal(|ui| {
+            if ui.button("Copy").clicked() {
+                ui.ctx().copy_text(records.join("\n"));
+            }
+
+            if ui.button("Clear").clicked() {
+                selph.logging.buffer.clear();
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.add(TextEdit::singleline(&mut selph.settings.log_path).desired_width(120.0).hint_text("log file"));
+
+            let mut writing = selph.logging.file.path().is_some();
+            if ui.checkbox(&mut writing, "Write").changed() {
+                if writing {
+                    match selph.logging.file.open(&PathBuf::from(&selph.settings.log_path)) {
+                        Ok(()) => info!("Logging to {}", selph.settings.log_path),
+                        Err(e) => selph.notifications.error(format!("Couldn't open log file {}: {}", selph.settings.log_path, e)),
+                    }
+                } else {
+                    selph.logging.file.close();
+                }
+            }
+        });
+    });
+}
+
+fn beacon_capture_ui(ui: &mut egui::Ui, selph: &mut TriangleGator) {
+    ui.collapsing("Beacon Capture", |ui| {
+        ui.horizontal(|ui| {
+            ui.add(TextEdit::singleline(&mut selph.capture_path).desired_width(140.0).hint_text("capture .pcap / .pcapng"));
+
+            if ui.button("Load").clicked() {
+                match capture::read_file(&PathBuf::from(&selph.capture_path)) {
+                    Ok(beacons) => selph.beacons = beacons,
+                    Err(e) => selph.notifications.error(format!("Couldn't read capture {}: {}. It needs radiotap headers.", selph.capture_path, e)),
+                }
+            }
+        });
+
+        ui.horizontal(|ui| {
+            if selph.live_capture.is_some() {
+                if ui.button("Stop").clicked() {
+                    selph.live_capture = None;
+                }
+            } else if ui.button("Monitor").on_hover_text("Capture on the selected interface, which must be in monitor mode").clicked() {
+                match current_interface(selph).map(|interface| interface.name.clone()) {
+                    Some(interface) => match LiveCapture::start(&interface) {
+                        Ok(live_capture) => selph.live_capture = Some(live_capture),
+                        Err(e) => selph.notifications.error(format!("Couldn't start tcpdump on {}: {}. Is tcpdump installed, and may it capture?", interface, e)),
+                    },
+                    None => selph.notifications.raise(Problem::NoInterface),
+                }
+            }
+
+            if ui.add_enabled(!selph.beacons.is_empty(), Button::new("Clear")).clicked() {
+                selph.beacons.clear();
+            }
+
+            ui.label(format!("{} beacons", selph.beacons.len()));
+        });
+
+        let summaries = capture::summarise(&selph.beacons);
+        let selected_text = selph.beacon_bssid.clone().unwrap_or(String::from("Access point"));
+
+        egui::ComboBox::from_id_salt("beacon_bssid").selected_text(selected_text).width(200.0).show_ui(ui, |ui| {
+            for summary in summaries.iter() {
+                let label = format!(
+                    "{} {}  {:.1} dBm ({})",
+                    summary.bssid,
+                    summary.ssid.as_deref().unwrap_or("<hidden>"),
+                    summary.mean_dbm,
+                    summary.frames,
+                );
+                ui.selectable_value(&mut selph.beacon_bssid, Some(summary.bssid.clone()), label);
+            }
+        });
+
+        let heard = selph.beacon_bssid.as_ref().is_some_and(|bssid| summaries.iter().any(|summary| &summary.bssid == bssid));
+
+        if ui.add_enabled(selph.selected_point.is_some() && heard, Button::new("Use For Point")).clicked() {
+            let net_info = capture::net_info_for(&selph.beacons, selph.beacon_bssid.as_ref().unwrap(), capture::DEFAULT_TX_POWER);
+            let index = selph.selected_point.unwrap();
+
+            measure_point(selph, index, Some(net_info));
+
+            // The next point gets its own beacons
+            selph.beacons.clear();
+            selph.selected_point = None;
+        }
+    });
+}
+
+fn sensor_ui(ui: &mut egui::Ui, selph: &mut TriangleGator) {
+    ui.collapsing("Sensors", |ui| {
+        ui.horizontal(|ui| {
+            ui.label("Port");
+            ui.add_enabled(selph.collector.is_none(), DragValue::new(&mut selph.settings.collector_port).speed(1));
+
+            if selph.collector.is_some() {
+                if ui.button("Stop").clicked() {
+                    selph.collector = None;
+                }
+            } else if ui.button("Listen").clicked() {
+                match Collector::bind(("0.0.0.0", selph.settings.collector_port), sensor::DEFAULT_MAX_AGE) {
+                    Ok(collector) => selph.collector = Some(collector),
+                    Err(e) => selph.notifications.error(format!("Couldn't listen on port {}: {}. Try another port.", selph.settings.collector_port, e)),
+                }
+            }
+
+            ui.checkbox(&mut selph.live_solve, "Live Solve");
+        });
+
+        let Some(collector) = &selph.collector else {
+            return;
+        };
+
+        egui::Grid::new("sensors").striped(true).show(ui, |ui| {
+            let unit = selph.session.unit.suffix();
+
+            for (sensor_id, position, signal_level, age) in collector.get_sensors() {
+                let stale = age > collector.max_age().as_secs_f32();
+
+                ui.label(sensor_id);
+                ui.label(format!("({:.1}, {:.1}) {}", position.x, position.y, unit));
+                ui.label(format!("{:.1} dBm", signal_level));
+                ui.colored_label(if stale { Color32::RED } else { Color32::GREEN }, format!("{:.1} s", age));
+                ui.end_row();
+            }
+        });
+    });
+}
+
+fn tracking_ui(ui: &mut egui::Ui, selph: &mut TriangleGator) {
+    let unit = selph.session.unit.suffix();
+
+    ui.collapsing("Tracking", |ui| {
+        ui.horizontal(|ui| {
+            if ui.checkbox(&mut selph.tracking, "Track").changed() && selph.tracking {
+                selph.tracker.reset();
+                selph.tracking_started = Instant::now();
+            }
+
+            if ui.button("Reset").clicked() {
+                selph.tracker.reset();
+                selph.tracking_started = Instant::now();
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Accel");
+            ui.add(DragValue::new(&mut selph.tracker.process_noise).speed(0.05).range(RangeInclusive::new(0.0, 50.0)).suffix(format!(" {}/s²", unit)));
+            ui.label("RSSI σ");
+            ui.add(DragValue::new(&mut selph.tracker.rssi_noise_db).speed(0.1).range(RangeInclusive::new(0.1, 20.0)).suffix(" dB"));
+        });
+
+        if let Some(estimate) = selph.tracker.estimate() {
+            let (major, minor, _) = estimate.ellipse(2.0);
+            ui.label(format!("({:.2}, {:.2}) {}  2σ: {:.2} × {:.2} {}", estimate.x, estimate.y, unit, major, minor, unit));
+        }
+    });
+}
+
+// Solves the session and keeps a copy for export. Raises a banner if enough points are tested but they lie on a line.
+fn solve_session(selph: &mut TriangleGator) -> Option<Location> {
+    selph.trilat_calc.set_path_loss_exponent(selph.session.path_loss_exponent);
+    selph.trilat_calc.set_unit(selph.session.unit);
+
+    let location = selph.trilat_calc.get_location(&selph.session.points);
+    let usable = selph.session.points.iter().filter(|point| selph.trilat_calc.is_usable(point)).count();
+
+    if location.is_none() && usable >= 3 {
+        selph.notifications.raise(Problem::SingularGeometry);
+    } else {
+        selph.notifications.resolve(&Problem::SingularGeometry);
+    }
+
+    selph.session.location = location.clone();
+    // Nothing's selected once reset, keep the SSID the readings were taken of
+    if let Some(network) = selph.network_manager.get_selected_network().as_ref() {
+        selph.session.ssid = Some(network.ssid.clone());
+    }
+    selph.solved_session = Some(selph.session.clone());
+    selph.solve_key = solve_key(&selph.session);
+
+    if selph.show_sweep {
+        selph.sweep = selph.session.exponent_sweep(SWEEP_EXPONENTS.0, SWEEP_EXPONENTS.1, SWEEP_STEPS);
+    }
+
+    return location;
+}
+
+// Everything the solver reads, to tell when the estimate is out of date
+fn solve_key(session: &Session) -> Vec<Option<f32>> {
+    let mut key = vec![Some(session.path_loss_exponent), Some(session.unit.to_metres(1.0))];
+
+    for point in session.points.iter() {
+        let net_info = point.net_info.as_ref();
+
+        key.extend([
+            Some(point.x),
+            Some(point.y),
+            Some(point.z),
+            net_info.and_then(|net_info| net_info.measured_power),
+            net_info.and_then(|net_info| net_info.tx_power),
+            Some(if point.excluded { 1.0 } else { 0.0 }),
+        ]);
+    }
+
+    return key;
+}
+
+// Once calculated, solves again whenever the exponent, points or readings change, including by undo
+fn live_recompute(selph: &mut TriangleGator) {
+    if selph.solved_session.is_none() || (selph.collector.is_some() && selph.live_solve) {
+        return;
+    }
+
+    if solve_key(&selph.session) != selph.solve_key {
+        if let Some(location) = solve_session(selph) {
+            debug!(uncertainty = location.uncertainty, "Recomputed network location: ({:.2}, {:.2}) {}", location.x, location.y, selph.session.unit.suffix());
+        }
+    }
+}
+
+// How far the estimate wanders as the exponent sweeps, since the right exponent for a building is a guess
+fn sensitivity_ui(ui: &mut egui::Ui, selph: &mut TriangleGator) {
+    let (from, to) = SWEEP_EXPONENTS;
+
+    ui.horizontal(|ui| {
+        if ui.checkbox(&mut selph.show_sweep, "Sensitivity").on_hover_text(format!("Draw the estimate's path as the exponent sweeps from {} to {}", from, to)).changed() && selph.show_sweep {
+            selph.sweep = selph.session.exponent_sweep(from, to, SWEEP_STEPS);
+        }
+
+        if let (true, Some((_, first)), Some((_, last))) = (selph.show_sweep, selph.sweep.first(), selph.sweep.last()) {
+            let spread = ((last.x - first.x).powi(2) + (last.y - first.y).powi(2)).sqrt();
+            ui.label(format!("moves {:.2} {} across the sweep", spread, selph.session.unit.suffix()));
+        }
+    });
+}
+
+// The estimate's path across the exponent sweep, labelled at each whole exponent
+fn plot_sweep(plot_ui: &mut PlotUi, sweep: &[(f32, Location)]) {
+    let path: Vec<[f64; 2]> = sweep.iter().map(|(_, location)| [f64::from(location.x), f64::from(location.y)]).collect();
+    plot_ui.line(Line::new(PlotPoints::from(path)).color(Color32::from_rgb(255, 120, 200)).name("Exponent sweep").allow_hover(false));
+
+    for (exponent, location) in sweep.iter().filter(|(exponent, _)| (exponent - exponent.round()).abs() < 0.01) {
+        let position = PlotPoint::new(location.x, location.y);
+
+        plot_ui.points(Points::new(vec![[position.x, position.y]]).radius(3.0).color(Color32::from_rgb(255, 120, 200)).allow_hover(false));
+        plot_ui.text(Text::new(position, format!("  n={}", exponent.round())).anchor(egui::Align2::LEFT_CENTER).color(Color32::from_rgb(255, 120, 200)));
+    }
+}
+
+// Stores a reading for the point, undoably, and feeds it to the filters
+fn measure_point(selph: &mut TriangleGator, index: usize, net_info: Option<NetInfo>) {
+    let before = selph.session.points[index].net_info.clone();
+    selph.history.apply(&mut selph.session, Edit::Measure { index, before, after: net_info });
+
+    feed_measurements(selph, &[selph.session.points[index].clone()]);
+}
+
+// Ctrl+Z undoes, Ctrl+Y or Ctrl+Shift+Z redoes. Left alone while typing, so text fields keep their own undo.
+fn undo_shortcuts(ctx: &egui::Context, selph: &mut TriangleGator) {
+    if ctx.wants_keyboard_input() {
+        return;
+    }
+
+    let (redo, undo) = ctx.input_mut(|i| {
+        let redo = i.consume_shortcut(&KeyboardShortcut::new(Modifiers::COMMAND | Modifiers::SHIFT, Key::Z))
+            || i.consume_shortcut(&KeyboardShortcut::new(Modifiers::COMMAND, Key::Y));
+        let undo = i.consume_shortcut(&KeyboardShortcut::new(Modifiers::COMMAND, Key::Z));
+        (redo, undo)
+    });
+
+    if undo {
+        undo_edit(selph);
+    }
+    if redo {
+        redo_edit(selph);
+    }
+}
+
+fn undo_buttons_ui(ui: &mut egui::Ui, selph: &mut TriangleGator) {
+    let undo_hint = selph.history.next_undo().map(|edit| format!("Undo {} (Ctrl+Z)", edit));
+    if ui.add_enabled(undo_hint.is_some(), Button::new("Undo")).on_hover_text(undo_hint.unwrap_or_default()).clicked() {
+        undo_edit(selph);
+    }
+
+    let redo_hint = selph.history.next_redo().map(|edit| format!("Redo {} (Ctrl+Y)", edit));
+    if ui.add_enabled(redo_hint.is_some(), Button::new("Redo")).on_hover_text(redo_hint.unwrap_or_default()).clicked() {
+        redo_edit(selph);
+    }
+}
+
+fn undo_edit(selph: &mut TriangleGator) {
+    if let Some(edit) = selph.history.undo(&mut selph.session) {
+        info!("Undid {}", edit);
+        after_history_change(selph);
+    }
+}
+
+fn redo_edit(selph: &mut TriangleGator) {
+    if let Some(edit) = selph.history.redo(&mut selph.session) {
+        info!("Redid {}", edit);
+        after_history_change(selph);
+    }
+}
+
+// Points may have come or gone, so drop anything pointing at them
+fn after_history_change(selph: &mut TriangleGator) {
+    if selph.selected_point.is_some_and(|index| index >= selph.session.points.len()) {
+        selph.selected_point = None;
+    }
+    selph.dragged_point = None;
+    selph.context_point = None;
+
+    // Undoing a reset brings the estimate back, so solve again to keep the exportable copy and live
+    // recompute going. Redoing it leaves nothing to solve.
+    if selph.session.location.is_some() {
+        solve_session(selph);
+    } else if selph.session.points.iter().all(|point| point.net_info.is_none()) {
+        selph.solved_session = None;
+    }
+
+    if let Some(solved_session) = selph.solved_session.as_mut() {
+        if solved_session.unit != selph.session.unit {
+            solved_session.set_unit(selph.session.unit);
+        }
+    }
+}
+
+// Feeds measurements taken now to the tracker and particle filter, whichever are running
+fn feed_measurements(selph: &mut TriangleGator, points: &[Point]) {
+    let calc = selph.session.calculator();
+
+    if selph.tracking {
+        let time = selph.tracking_started.elapsed().as_secs_f64();
+        selph.tracker.update(time, points, &calc);
+    }
+
+    if selph.particles {
+        selph.particle_filter.update(points, &selph.session.walls, &calc);
+    }
+}
+
+fn particle_filter_ui(ui: &mut egui::Ui, selph: &mut TriangleGator) {
+    let unit = selph.session.unit.suffix();
+
+    ui.collapsing("Particle Filter", |ui| {
+        ui.horizontal(|ui| {
+            if ui.checkbox(&mut selph.particles, "Particles").changed() {
+                selph.particle_filter.reset();
+            }
+
+            // Runs the filter over every reading in the session at once. Starts over first, since readings
+            // are fed as they're taken and counting them twice would make the cloud overconfident.
+            if ui.add_enabled(selph.particles, Button::new("Update")).clicked() {
+                let calc = selph.session.calculator();
+                selph.particle_filter.reset();
+                selph.particle_filter.update(&selph.session.points, &selph.session.walls, &calc);
+            }
+
+            if ui.button("Reset").clicked() {
+                selph.particle_filter.reset();
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("RSSI σ");
+            ui.add(DragValue::new(&mut selph.particle_filter.rssi_noise_db).speed(0.1).range(RangeInclusive::new(0.1, 20.0)).suffix(" dB"));
+            ui.label("Jitter");
+            ui.add(DragValue::new(&mut selph.particle_filter.jitter).speed(0.1).range(RangeInclusive::new(0.0, 50.0)).suffix(format!(" {}", unit)));
+        });
+
+        ui.horizontal(|ui| {
+            if ui.toggle_value(&mut selph.drawing_walls, "Draw Walls").changed() {
+                selph.wall_start = None;
+                selph.selected_point = None;
+            }
+
+            ui.add(DragValue::new(&mut selph.settings.wall_attenuation).speed(0.5).range(RangeInclusive::new(0.0, 60.0)).suffix(" dB"));
+
+            if ui.add_enabled(!selph.session.walls.is_empty(), Button::new("Clear Walls")).clicked() {
+                let before = selph.session.clone();
+                selph.session.walls.clear();
+                selph.history.record(Edit::Replace { description: "clear walls", before: Box::new(before), after: Box::new(selph.session.clone()) });
+            }
+        });
+
+        if let Some((x, y)) = selph.particle_filter.estimate() {
+            ui.label(format!("Weighted mean: ({:.2}, {:.2}) {}", x, y, unit));
+        }
+    });
+}
+
+// Draws the particle cloud and its weighted mean
+fn plot_particles(plot_ui: &mut PlotUi, particle_filter: &ParticleFilter) {
+    let cloud: Vec<[f64; 2]> = particle_filter.get_particles().iter().map(|particle| [f64::from(particle.x), f64::from(particle.y)]).collect();
+    plot_ui.points(Points::new(cloud).radius(1.0).color(Color32::from_rgba_unmultiplied(255, 140, 0, 90)).name("Particles").allow_hover(false));
+
+    if let Some((x, y)) = particle_filter.estimate() {
+        plot_point(plot_ui, x, y);
+    }
+}
+
+fn plot_walls(plot_ui: &mut PlotUi, walls: &[Wall]) {
+    for wall in walls {
+        let segment = vec![[f64::from(wall.start[0]), f64::from(wall.start[1])], [f64::from(wall.end[0]), f64::from(wall.end[1])]];
+        plot_ui.line(Line::new(PlotPoints::from(segment)).color(Color32::LIGHT_GRAY).width(3.0).allow_hover(false));
+    }
+}
+
+// Draws the filtered track, its latest position and that position's 2σ covariance ellipse
+fn plot_track(plot_ui: &mut PlotUi, tracker: &Tracker) {
+    let track: Vec<[f64; 2]> = tracker.get_track().iter().map(|track_point| [f64::from(track_point.x), f64::from(track_point.y)]).collect();
+    plot_ui.line(Line::new(PlotPoints::from(track)).color(Color32::GOLD).name("Track").allow_hover(false));
+
+    let Some(estimate) = tracker.estimate() else {
+        return;
+    };
+
+    let (major, minor, angle) = estimate.ellipse(2.0);
+    let ellipse: Vec<[f64; 2]> = (0..=RANGE_SEGMENTS).map(|i| {
+        let t = std::f32::consts::TAU * i as f32 / RANGE_SEGMENTS as f32;
+        let (u, v) = (major * t.cos(), minor * t.sin());
+        [f64::from(estimate.x + u * angle.cos() - v * angle.sin()), f64::from(estimate.y + u * angle.sin() + v * angle.cos())]
+    }).collect();
+
+    plot_ui.polygon(Polygon::new(PlotPoints::from(ellipse)).fill_color(Color32::from_rgba_unmultiplied(255, 215, 0, 30)).stroke(Stroke::new(1.0, Color32::GOLD)).allow_hover(false));
+    plot_point(plot_ui, estimate.x, estimate.y);
+}
+
+fn load_floor_plan(ctx: &egui::Context, path: &str, unit: DistanceUnit) -> Result<(FloorPlan, TextureHandle), image::ImageError> {
+    let image = image::open(path)?.into_rgba8();
+    let (width, height) = image.dimensions();
+
+    let color_image = ColorImage::from_rgba_unmultiplied([width as usize, height as usize], image.as_raw());
+    let texture = ctx.load_texture("floor_plan", color_image, TextureOptions::LINEAR);
+
+    return Ok((FloorPlan::new(PathBuf::from(path), width, height, unit), texture));
+}
+
+fn get_selected_netinfo(network_manager: &NetworkManager, sample_scale: u16, sample_length: u64) -> NetInfo{
+    return network_manager.ping_network(sample_scale, sample_length);
+}
+
+fn reset_calc(selph: &mut TriangleGator) {
+    selph.network_manager.reset_network_manager();
+    selph.network_password = Zeroizing::default();
+    selph.enterprise.password = Zeroizing::default();
+    selph.session.location = None;
+    selph.solved_session = None;
+
+    reset_netinfo(selph);
+}
+
+fn reset_netinfo(selph: &mut TriangleGator) {
+    for point in selph.session.points.iter_mut() {
+        point.net_info = None;
+    }
+}
+
+fn custom_window_frame(ctx: &egui::Context, title: &str, add_contents: impl FnOnce(&mut egui::Ui)) {
+    use egui::{CentralPanel, UiBuilder};
+
+    let panel_frame = egui::Frame::new()
+        .fill(ctx.style().visuals.window_fill())
+        .corner_radius(10)
+        .stroke(ctx.style().visuals.widgets.noninteractive.fg_stroke)
+        .outer_margin(1); // so the stroke is within the bounds
+
+    CentralPanel::default().frame(panel_frame).show(ctx, |ui| {
+        let app_rect = ui.max_rect();
+
+        let title_bar_height = 32.0;
+        let title_bar_rect = {
+            let mut rect = app_rect;
+            rect.max.y = rect.min.y + title_bar_height;
+            rect
+        };
+        title_bar_ui(ui, title_bar_rect, title);
+
+        // Add the contents:
+        let content_rect = {
+            let mut rect = app_rect;
+            rect.min.y = title_bar_rect.max.y;
+            rect
+        }
+        .shrink(4.0);
+        let mut content_ui = ui.new_child(UiBuilder::new().max_rect(content_rect));
+        add_contents(&mut content_ui);
+
+        resize_edges_ui(ui, app_rect);
+    });
+}
+
+// Without decorations the window has no native border to drag, so the edges and corners resize it
+fn resize_edges_ui(ui: &mut egui::Ui, app_rect: eframe::epaint::Rect) {
+    use egui::{CursorIcon, Id, PointerButton, Rect, ResizeDirection, Sense};
+
+    let grip = 5.0;
+    let (left, right, top, bottom) = (app_rect.left(), app_rect.right(), app_rect.top(), app_rect.bottom());
+
+    let edges = [
+        (Rect::from_min_max(egui::pos2(left, top), egui::pos2(left + grip, top + grip)), ResizeDirection::NorthWest, CursorIcon::ResizeNorthWest),
+        (Rect::from_min_max(egui::pos2(right - grip, top), egui::pos2(right, top + grip)), ResizeDirection::NorthEast, CursorIcon::ResizeNorthEast),
+        (Rect::from_min_max(egui::pos2(left, bottom - grip), egui::pos2(left + grip, bottom)), ResizeDirection::SouthWest, CursorIcon::ResizeSouthWest),
+        (Rect::from_min_max(egui::pos2(right - grip, bottom - grip), egui::pos2(right, bottom)), ResizeDirection::SouthEast, CursorIcon::ResizeSouthEast),
+        (Rect::from_min_max(egui::pos2(left + grip, top), egui::pos2(right - grip, top + grip)), ResizeDirection::North, CursorIcon::ResizeNorth),
+        (Rect::from_min_max(egui::pos2(left + grip, bottom - grip), egui::pos2(right - grip, bottom)), ResizeDirection::South, CursorIcon::ResizeSouth),
+        (Rect::from_min_max(egui::pos2(left, top + grip), egui::pos2(left + grip, bottom - grip)), ResizeDirection::West, CursorIcon::ResizeWest),
+        (Rect::from_min_max(egui::pos2(right - grip, top + grip), egui::pos2(right, bottom - grip)), ResizeDirection::East, CursorIcon::ResizeEast),
+    ];
+
+    for (index, (rect, direction, cursor)) in edges.into_iter().enumerate() {
+        let response = ui.interact(rect, Id::new(("resize_edge", index)), Sense::drag()).on_hover_cursor(cursor);
+
+        if response.drag_started_by(PointerButton::Primary) {
+            ui.ctx().send_viewport_cmd(ViewportCommand::BeginResize(direction));
+        }
+    }
+}
+
+fn title_bar_ui(ui: &mut egui::Ui, title_bar_rect: eframe::epaint::Rect, title: &str) {
+    use egui::{vec2, Align2, FontId, Id, PointerButton, Sense, UiBuilder};
+
+    let painter = ui.painter();
+
+    let title_bar_response = ui.interact(
+        title_bar_rect,
+        Id::new("title_bar"),
+        Sense::click_and_drag(),
+    );
+
+    // Paint the title:
+    painter.text(
+        title_bar_rect.center(),
+        Align2::CENTER_CENTER,
+        title,
+        FontId::proportional(20.0),
+        ui.style().visuals.text_color(),
+    );
+
+    // Paint the line under the title:
+    painter.line_segment(
+        [
+            title_bar_rect.left_bottom() + vec2(1.0, 0.0),
+            title_bar_rect.right_bottom() + vec2(-1.0, 0.0),
+        ],
+        ui.visuals().widgets.noninteractive.bg_stroke,
+    );
+
+    // Interact with the title bar (drag to move window):
+    if title_bar_response.double_clicked() {
+        let is_maximized = ui.input(|i| i.viewport().maximized.unwrap_or(false));
+        ui.ctx()
+            .send_viewport_cmd(ViewportCommand::Maximized(!is_maximized));
+    }
+
+    if title_bar_response.drag_started_by(PointerButton::Primary) {
+        ui.ctx().send_viewport_cmd(ViewportCommand::StartDrag);
+    }
+
+    ui.allocate_new_ui(
+        UiBuilder::new()
+            .max_rect(title_bar_rect)
+            .layout(egui::Layout::right_to_left(egui::Align::Center)),
+        |ui| {
+            ui.spacing_mut().item_spacing.x = 0.0;
+            ui.visuals_mut().button_frame = false;
+            ui.add_space(8.0);
+            close_ui(ui);
+        },
+    );
+}
+
+/// Show close button for the native window.
+fn close_ui(ui: &mut egui::Ui) {
+    use egui::{Button, RichText};
+
+    let button_height = 12.0;
+
+    let close_response = ui
+        .add(Button::new(RichText::new("❌").size(button_height)))
+        .on_hover_text("Close the window");
+    if close_response.clicked() {
+        ui.ctx().send_viewport_cmd(ViewportCommand::Close);
+    }
+}
\ No newline at end of file
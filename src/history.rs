@@ -0,0 +1,298 @@
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::geo::GeoOrigin;
+use crate::models::{NetInfo, Point, Wall};
+use crate::session::Session;
+
+const HISTORY_LIMIT: usize = 200; // Edits kept to undo, oldest dropped first
+const MERGE_WINDOW: Duration = Duration::from_secs(1); // Nudges to the same value this close together undo as one
+
+// A change to the session that can be undone. Only the survey is covered: its points, readings, walls,
+// exponent, units, origin and scale. Preferences from the Settings panel, sample scale and length among
+// them, are kept between launches apart from any survey and aren't undone.
+#[derive(Clone)]
+pub enum Edit {
+    AddPoint { index: usize, point: Point },
+    DeletePoint { index: usize, point: Point },
+    MovePoint { index: usize, from: [f32; 3], to: [f32; 3] }, // x, y, z
+    Measure { index: usize, before: Option<NetInfo>, after: Option<NetInfo> },
+    SetExcluded { index: usize, excluded: bool },
+    SetExponent { from: f32, to: f32 },
+    SetOrigin { from: Option<GeoOrigin>, to: Option<GeoOrigin> },
+    AddWall { index: usize, wall: Wall },
+    // Anything touching the whole session at once, e.g. a reset or changing units
+    Replace { description: &'static str, before: Box<Session>, after: Box<Session> },
+}
+
+impl Edit {
+    pub fn apply(&self, session: &mut Session) {
+        match self {
+            Edit::AddPoint { index, point } => session.points.insert(*index, point.clone()),
+            Edit::DeletePoint { index, .. } => {
+                session.points.remove(*index);
+            }
+            Edit::MovePoint { index, to, .. } => set_position(&mut session.points[*index], *to),
+            Edit::Measure { index, after, .. } => session.points[*index].net_info = after.clone(),
+            Edit::SetExcluded { index, excluded } => session.points[*index].excluded = *excluded,
+            Edit::SetExponent { to, .. } => session.path_loss_exponent = *to,
+            Edit::SetOrigin { to, .. } => session.origin = to.clone(),
+            Edit::AddWall { index, wall } => session.walls.insert(*index, wall.clone()),
+            Edit::Replace { after, .. } => *session = (**after).clone(),
+        }
+    }
+
+    pub fn revert(&self, session: &mut Session) {
+        match self {
+            Edit::AddPoint { index, .. } => {
+                session.points.remove(*index);
+            }
+            Edit::DeletePoint { index, point } => session.points.insert(*index, point.clone()),
+            Edit::MovePoint { index, from, .. } => set_position(&mut session.points[*index], *from),
+            Edit::Measure { index, before, .. } => session.points[*index].net_info = before.clone(),
+            Edit::SetExcluded { index, excluded } => session.points[*index].excluded = !*excluded,
+            Edit::SetExponent { from, .. } => session.path_loss_exponent = *from,
+            Edit::SetOrigin { from, .. } => session.origin = from.clone(),
+            Edit::AddWall { index, .. } => {
+                session.walls.remove(*index);
+            }
+            Edit::Replace { before, .. } => *session = (**before).clone(),
+        }
+    }
+
+    // Folds a following edit of the same thing into this one, true if it did
+    fn merge(&mut self, next: &Edit) -> bool {
+        match (self, next) {
+            (Edit::MovePoint { index, to, .. }, Edit::MovePoint { index: next_index, to: next_to, .. }) if index == next_index => {
+                *to = *next_to;
+                return true;
+            }
+            (Edit::SetExponent { to, .. }, Edit::SetExponent { to: next_to, .. }) => {
+                *to = *next_to;
+                return true;
+            }
+            (Edit::SetOrigin { to: to @ Some(_), .. }, Edit::SetOrigin { to: next_to @ Some(_), .. }) => {
+                *to = next_to.clone();
+                return true;
+            }
+            _ => return false,
+        }
+    }
+}
+
+impl fmt::Display for Edit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            Edit::AddPoint { index, .. } => write!(f, "add point {}", index + 1),
+            Edit::DeletePoint { index, .. } => write!(f, "delete point {}", index + 1),
+            Edit::MovePoint { index, .. } => write!(f, "move point {}", index + 1),
+            Edit::Measure { index, .. } => write!(f, "measure point {}", index + 1),
+            Edit::SetExcluded { index, excluded: true } => write!(f, "exclude point {}", index + 1),
+            Edit::SetExcluded { index, excluded: false } => write!(f, "include point {}", index + 1),
+            Edit::SetExponent { to, .. } => write!(f, "set path loss exponent to {:.2}", to),
+            Edit::SetOrigin { to: Some(_), .. } => write!(f, "set origin"),
+            Edit::SetOrigin { to: None, .. } => write!(f, "remove georeference"),
+            Edit::AddWall { .. } => write!(f, "add wall"),
+            Edit::Replace { description, .. } => write!(f, "{}", description),
+        };
+    }
+}
+
+// Undo and redo stacks for the session's edits
+#[derive(Default)]
+pub struct History {
+    undo: Vec<Edit>,
+    redo: Vec<Edit>,
+    last_recorded: Option<Instant>,
+}
+
+impl History {
+    // Makes the edit and records it
+    pub fn apply(&mut self, session: &mut Session, edit: Edit) {
+        edit.apply(session);
+        self.record(edit);
+    }
+
+    // Records an edit that's already been made, e.g. a point dragged across the plot
+    pub fn record(&mut self, edit: Edit) {
+        self.redo.clear();
+
+        let merge = self.last_recorded.is_some_and(|last_recorded| last_recorded.elapsed() < MERGE_WINDOW);
+        self.last_recorded = Some(Instant::now());
+
+        if let (true, Some(last)) = (merge, self.undo.last_mut()) {
+            if last.merge(&edit) {
+                return;
+            }
+        }
+
+        if self.undo.len() == HISTORY_LIMIT {
+            self.undo.remove(0);
+        }
+        self.undo.push(edit);
+    }
+
+    // Returns the edit undone, if there was one
+    pub fn undo(&mut self, session: &mut Session) -> Option<&Edit> {
+        let edit = self.undo.pop()?;
+        edit.revert(session);
+
+        self.last_recorded = None;
+        self.redo.push(edit);
+
+        return self.redo.last();
+    }
+
+    pub fn redo(&mut self, session: &mut Session) -> Option<&Edit> {
+        let edit = self.redo.pop()?;
+        edit.apply(session);
+
+        self.last_recorded = None;
+        self.undo.push(edit);
+
+        return self.undo.last();
+    }
+
+    pub fn next_undo(&self) -> Option<&Edit> {
+        return self.undo.last();
+    }
+
+    pub fn next_redo(&self) -> Option<&Edit> {
+        return self.redo.last();
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.last_recorded = None;
+    }
+}
+
+fn set_position(point: &mut Point, [x, y, z]: [f32; 3]) {
+    point.x = x;
+    point.y = y;
+    point.z = z;
+}
+
+pub fn position(point: &Point) -> [f32; 3] {
+    return [point.x, point.y, point.z];
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session_with_points(count: usize) -> Session {
+        let mut session = Session::default();
+        session.points.clear();
+        for i in 0..count {
+            session.points.push(Point::new(i as f32, 0.0, None));
+        }
+
+        return session;
+    }
+
+    fn xs(session: &Session) -> Vec<f32> {
+        return session.points.iter().map(|point| point.x).collect();
+    }
+
+    #[test]
+    fn undoes_and_redoes() {
+        let mut session = session_with_points(2);
+        let mut history = History::default();
+
+        history.apply(&mut session, Edit::AddPoint { index: 1, point: Point::new(5.0, 5.0, None) });
+        history.apply(&mut session, Edit::SetExcluded { index: 0, excluded: true });
+        assert_eq!(xs(&session), [0.0, 5.0, 1.0]);
+        assert!(session.points[0].excluded);
+
+        assert_eq!(history.undo(&mut session).unwrap().to_string(), "exclude point 1");
+        assert!(!session.points[0].excluded);
+        assert_eq!(history.undo(&mut session).unwrap().to_string(), "add point 2");
+        assert_eq!(xs(&session), [0.0, 1.0]);
+        assert!(history.undo(&mut session).is_none());
+
+        assert_eq!(history.redo(&mut session).unwrap().to_string(), "add point 2");
+        assert_eq!(xs(&session), [0.0, 5.0, 1.0]);
+        assert_eq!(history.next_redo().unwrap().to_string(), "exclude point 1");
+    }
+
+    #[test]
+    fn records_edits_already_made() {
+        let mut session = session_with_points(1);
+        let mut history = History::default();
+
+        let before = session.clone();
+        session.points.clear();
+        history.record(Edit::Replace { description: "reset", before: Box::new(before), after: Box::new(session.clone()) });
+
+        history.undo(&mut session);
+        assert_eq!(xs(&session), [0.0]);
+        history.redo(&mut session);
+        assert!(session.points.is_empty());
+    }
+
+    #[test]
+    fn merges_moves_of_the_same_point() {
+        let mut session = session_with_points(2);
+        let mut history = History::default();
+
+        history.apply(&mut session, Edit::MovePoint { index: 0, from: [0.0, 0.0, 0.0], to: [1.0, 1.0, 0.0] });
+        history.apply(&mut session, Edit::MovePoint { index: 0, from: [1.0, 1.0, 0.0], to: [2.0, 2.0, 0.0] });
+        history.apply(&mut session, Edit::MovePoint { index: 1, from: [1.0, 0.0, 0.0], to: [3.0, 0.0, 0.0] });
+
+        history.undo(&mut session);
+        assert_eq!(xs(&session), [2.0, 1.0]);
+
+        // Both nudges of the first point go at once
+        history.undo(&mut session);
+        assert_eq!(xs(&session), [0.0, 1.0]);
+        assert!(history.next_undo().is_none());
+    }
+
+    #[test]
+    fn keeps_moves_apart_outside_the_merge_window() {
+        let mut session = session_with_points(1);
+        let mut history = History::default();
+
+        history.apply(&mut session, Edit::MovePoint { index: 0, from: [0.0, 0.0, 0.0], to: [1.0, 0.0, 0.0] });
+        history.last_recorded = Instant::now().checked_sub(MERGE_WINDOW);
+        history.apply(&mut session, Edit::MovePoint { index: 0, from: [1.0, 0.0, 0.0], to: [2.0, 0.0, 0.0] });
+
+        history.undo(&mut session);
+        assert_eq!(xs(&session), [1.0]);
+        history.undo(&mut session);
+        assert_eq!(xs(&session), [0.0]);
+    }
+
+    #[test]
+    fn new_edits_clear_redo() {
+        let mut session = session_with_points(1);
+        let mut history = History::default();
+
+        let from = session.path_loss_exponent;
+        history.apply(&mut session, Edit::SetExponent { from, to: 4.0 });
+        history.undo(&mut session);
+        assert!(history.next_redo().is_some());
+
+        let point = session.points[0].clone();
+        history.apply(&mut session, Edit::DeletePoint { index: 0, point });
+        assert!(history.next_redo().is_none());
+        assert!(history.redo(&mut session).is_none());
+    }
+
+    #[test]
+    fn undoes_the_wall_it_added() {
+        let mut session = session_with_points(0);
+        let mut history = History::default();
+
+        let wall = |x: f32| Wall { start: [x, 0.0], end: [x, 10.0], attenuation_db: 5.0 };
+        session.walls = vec![wall(1.0), wall(2.0)];
+
+        history.apply(&mut session, Edit::AddWall { index: 1, wall: wall(9.0) });
+        assert_eq!(session.walls.iter().map(|wall| wall.start[0]).collect::<Vec<f32>>(), [1.0, 9.0, 2.0]);
+
+        history.undo(&mut session);
+        assert_eq!(session.walls.iter().map(|wall| wall.start[0]).collect::<Vec<f32>>(), [1.0, 2.0]);
+    }
+}
@@ -0,0 +1,165 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use serde::Serialize;
+
+// A wireless adapter, as found under /sys/class/net
+#[derive(Clone, Serialize)]
+pub struct WirelessInterface {
+    pub name: String,
+    pub driver: Option<String>,
+    pub mac: Option<String>,
+    pub phy: Option<String>, // e.g. phy0, what iw calls the radio behind the interface
+    pub capabilities: Vec<String>, // Bands, standards and modes the radio supports
+}
+
+impl WirelessInterface {
+    pub fn of(name: &str) -> WirelessInterface {
+        let device = Path::new("/sys/class/net").join(name);
+
+        let driver = fs::read_link(device.join("device/driver")).ok()
+            .and_then(|driver| driver.file_name().map(|name| name.to_string_lossy().into_owned()));
+        let mac = fs::read_to_string(device.join("address")).ok()
+            .map(|mac| mac.trim().to_lowercase());
+        let phy = fs::read_to_string(device.join("phy80211/name")).ok()
+            .map(|phy| phy.trim().to_string());
+
+        let capabilities = phy.as_deref().map(phy_capabilities).unwrap_or_default();
+
+        return WirelessInterface { name: String::from(name), driver, mac, phy, capabilities };
+    }
+
+    // One line summary for pickers and listings
+    pub fn describe(&self) -> String {
+        return format!(
+            "{} ({}, {})",
+            self.name,
+            self.driver.as_deref().unwrap_or("unknown driver"),
+            self.mac.as_deref().unwrap_or("unknown MAC"),
+        );
+    }
+}
+
+// Every interface with a wireless extension, sorted by name
+pub fn wireless_interfaces() -> Vec<WirelessInterface> {
+    let Ok(entries) = fs::read_dir("/sys/class/net") else {
+        return vec![];
+    };
+
+    let mut names: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().join("wireless").exists())
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+
+    return names.iter().map(|name| WirelessInterface::of(name)).collect();
+}
+
+// Asks iw what the radio can do. Empty if iw isn't installed.
+fn phy_capabilities(phy: &str) -> Vec<String> {
+    let Ok(output) = Command::new("iw").args(["phy", phy, "info"]).output() else {
+        return vec![];
+    };
+
+    return parse_phy_info(&String::from_utf8_lossy(&output.stdout));
+}
+
+fn parse_phy_info(info: &str) -> Vec<String> {
+    let mut capabilities = Vec::new();
+    let mut push = |capability: &str| {
+        if !capabilities.iter().any(|existing| existing == capability) {
+            capabilities.push(String::from(capability));
+        }
+    };
+
+    let mut in_modes = false;
+
+    for line in info.lines() {
+        let trimmed = line.trim();
+
+        match trimmed {
+            "Band 1:" => push("2.4 GHz"),
+            "Band 2:" => push("5 GHz"),
+            "Band 4:" => push("6 GHz"),
+            _ if trimmed.starts_with("HT Capabilities") => push("802.11n"),
+            _ if trimmed.starts_with("VHT Capabilities") => push("802.11ac"),
+            _ if trimmed.starts_with("HE Iftypes") => push("802.11ax"),
+            _ => {}
+        }
+
+        // The list of modes is indented under its heading, and ends at the next heading
+        if trimmed == "Supported interface modes:" {
+            in_modes = true;
+            continue;
+        }
+
+        if in_modes {
+            match trimmed.strip_prefix("* ") {
+                Some("monitor") => push("monitor"),
+                Some("AP") => push("AP"),
+                Some(_) => {}
+                None => in_modes = false,
+            }
+        }
+    }
+
+    return capabilities;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Trimmed from iw phy phy0 info on an AX200
+    const PHY_INFO: &str = "Wiphy phy0
+	wiphy index: 0
+	max # scan SSIDs: 20
+	Supported Ciphers:
+		* WEP40 (00-0f-ac:1)
+		* CCMP-128 (00-0f-ac:4)
+	Available Antennas: TX 0x3 RX 0x3
+	Supported interface modes:
+		 * managed
+		 * AP
+		 * AP/VLAN
+		 * monitor
+	Band 1:
+		Capabilities: 0x1ff2
+			HT20/HT40
+		HT Capabilities: 0x1ff2
+		HE Iftypes: managed
+			HE MAC Capabilities (0x780108a01240):
+	Band 2:
+		VHT Capabilities (0x039071f6):
+			Max MPDU length: 11454
+		HE Iftypes: managed
+	Band 4:
+		HE Iftypes: managed
+	Supported commands:
+		 * new_interface
+		 * set_interface
+";
+
+    #[test]
+    fn parses_phy_info() {
+        assert_eq!(parse_phy_info(PHY_INFO), ["AP", "monitor", "2.4 GHz", "802.11n", "802.11ax", "5 GHz", "802.11ac", "6 GHz"]);
+    }
+
+    #[test]
+    fn parses_a_radio_without_monitor_or_ap() {
+        let info = "Wiphy phy1
+	Supported interface modes:
+		 * managed
+		 * P2P-client
+	Band 1:
+		HT Capabilities: 0x11ef
+	Supported commands:
+		 * monitor
+";
+
+        assert_eq!(parse_phy_info(info), ["2.4 GHz", "802.11n"]);
+        assert!(parse_phy_info("").is_empty());
+    }
+}
@@ -0,0 +1,293 @@
+use std::collections::VecDeque;
+use std::fmt::{self, Write as _};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::field::RecordFields;
+use tracing_subscriber::fmt::format::{DefaultFields, Writer};
+use tracing_subscriber::fmt::{FormatFields, MakeWriter};
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::EnvFilter;
+
+// Our own info and up, only warnings from the GUI toolkit. RUST_LOG overrides it.
+pub const DEFAULT_FILTER: &str = "warn,triangle_gator=info,gator_cli=info";
+const LOG_CAPACITY: usize = 2000; // Records kept for the log panel, oldest dropped first
+
+// One event, as shown in the log panel
+#[derive(Clone)]
+pub struct LogRecord {
+    pub timestamp: SystemTime,
+    pub level: Level,
+    pub target: String,
+    pub spans: String, // e.g. "connect{ssid=Lab}:sample{interface=wlan0}"
+    pub message: String,
+}
+
+// Times are UTC, same as the stderr and file output, and labelled so nobody reads them as local
+impl fmt::Display for LogRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let seconds = self.timestamp.duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_secs()).unwrap_or(0);
+        let (hours, minutes, seconds) = ((seconds / 3600) % 24, (seconds / 60) % 60, seconds % 60);
+
+        write!(f, "{:02}:{:02}:{:02}Z {:>5} ", hours, minutes, seconds, self.level)?;
+
+        if !self.spans.is_empty() {
+            write!(f, "{}: ", self.spans)?;
+        }
+
+        return write!(f, "{}", self.message);
+    }
+}
+
+// The most recent records, shared between the subscriber and the GUI
+#[derive(Clone, Default)]
+pub struct LogBuffer {
+    records: Arc<Mutex<VecDeque<LogRecord>>>,
+}
+
+impl LogBuffer {
+    pub fn records(&self) -> Vec<LogRecord> {
+        return self.records.lock().unwrap().iter().cloned().collect();
+    }
+
+    pub fn clear(&self) {
+        self.records.lock().unwrap().clear();
+    }
+
+    fn push(&self, record: LogRecord) {
+        let mut records = self.records.lock().unwrap();
+
+        if records.len() == LOG_CAPACITY {
+            records.pop_front();
+        }
+        records.push_back(record);
+    }
+}
+
+// Log file that can be opened and closed while the subscriber is running. Writes go nowhere while it's closed.
+#[derive(Clone, Default)]
+pub struct LogFile {
+    file: Arc<Mutex<Option<(PathBuf, File)>>>,
+}
+
+impl LogFile {
+    // Appends to the file, creating it if needed
+    pub fn open(&self, path: &Path) -> io::Result<()> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        *self.file.lock().unwrap() = Some((path.to_path_buf(), file));
+
+        return Ok(());
+    }
+
+    pub fn close(&self) {
+        *self.file.lock().unwrap() = None;
+    }
+
+    pub fn path(&self) -> Option<PathBuf> {
+        return self.file.lock().unwrap().as_ref().map(|(path, _)| path.clone());
+    }
+}
+
+impl Write for LogFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        return match self.file.lock().unwrap().as_mut() {
+            Some((_, file)) => file.write(buf),
+            None => Ok(buf.len()),
+        };
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        return match self.file.lock().unwrap().as_mut() {
+            Some((_, file)) => file.flush(),
+            None => Ok(()),
+        };
+    }
+}
+
+impl<'a> MakeWriter<'a> for LogFile {
+    type Writer = LogFile;
+
+    fn make_writer(&'a self) -> LogFile {
+        return self.clone();
+    }
+}
+
+// Where the installed subscriber sends its records, besides stderr
+#[derive(Clone, Default)]
+pub struct Logging {
+    pub buffer: LogBuffer,
+    pub file: LogFile,
+}
+
+// Installs the global subscriber: stderr, the in-memory buffer and the (initially closed) log file.
+// Only the first call in a process takes effect.
+pub fn init(default_filter: &str) -> Logging {
+    let logging = Logging::default();
+
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(default_filter));
+
+    let result = tracing_subscriber::registry()
+        .with(filter)
+        .with(tracing_subscriber::fmt::layer().with_writer(io::stderr))
+        .with(tracing_subscriber::fmt::layer().with_writer(logging.file.clone()).with_ansi(false).fmt_fields(PlainFields))
+        .with(BufferLayer { buffer: logging.buffer.clone() })
+        .try_init();
+
+    if let Err(e) = result {
+        eprintln!("Logging was already set up: {}", e);
+    }
+
+    return logging;
+}
+
+// Span fields are formatted once per formatter type and shared between layers, so the file needs a
+// type of its own or it gets the colours formatted for stderr
+struct PlainFields;
+
+impl<'writer> FormatFields<'writer> for PlainFields {
+    fn format_fields<R: RecordFields>(&self, writer: Writer<'writer>, fields: R) -> fmt::Result {
+        return DefaultFields::new().format_fields(writer, fields);
+    }
+}
+
+struct BufferLayer {
+    buffer: LogBuffer,
+}
+
+// A span's fields, formatted once when it's created
+struct SpanFields(String);
+
+impl<S> Layer<S> for BufferLayer where S: Subscriber + for<'a> LookupSpan<'a> {
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let mut visitor = FieldVisitor::default();
+        attrs.record(&mut visitor);
+
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut().insert(SpanFields(visitor.fields));
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let mut visitor = FieldVisitor::default();
+        event.record(&mut visitor);
+
+        let mut spans = String::new();
+        if let Some(scope) = ctx.event_scope(event) {
+            for span in scope.from_root() {
+                if !spans.is_empty() {
+                    spans.push(':');
+                }
+                spans.push_str(span.name());
+
+                if let Some(SpanFields(fields)) = span.extensions().get::<SpanFields>() {
+                    if !fields.is_empty() {
+                        let _ = write!(spans, "{{{}}}", fields.trim_start());
+                    }
+                }
+            }
+        }
+
+        let message = if visitor.fields.is_empty() { visitor.message } else { format!("{}{}", visitor.message, visitor.fields) };
+
+        self.buffer.push(LogRecord {
+            timestamp: SystemTime::now(),
+            level: *event.metadata().level(),
+            target: String::from(event.metadata().target()),
+            spans,
+            message,
+        });
+    }
+}
+
+#[derive(Default)]
+struct FieldVisitor {
+    message: String,
+    fields: String, // " key=value" for every field besides the message
+}
+
+impl Visit for FieldVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message = String::from(value);
+        } else {
+            let _ = write!(self.fields, " {}={}", field.name(), value);
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            self.message = format!("{:?}", value);
+        } else {
+            let _ = write!(self.fields, " {}={:?}", field.name(), value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn record(message: &str) -> LogRecord {
+        return LogRecord {
+            timestamp: UNIX_EPOCH + Duration::from_secs(3 * 86400 + 13 * 3600 + 5 * 60 + 9),
+            level: Level::INFO,
+            target: String::from("triangle_gator"),
+            spans: String::from(""),
+            message: String::from(message),
+        };
+    }
+
+    #[test]
+    fn formats_records() {
+        let mut record = record("Connected");
+        assert_eq!(record.to_string(), "13:05:09Z  INFO Connected");
+
+        record.level = Level::ERROR;
+        record.spans = String::from("connect{ssid=Lab}");
+        assert_eq!(record.to_string(), "13:05:09Z ERROR connect{ssid=Lab}: Connected");
+    }
+
+    #[test]
+    fn buffer_keeps_the_most_recent_records() {
+        let buffer = LogBuffer::default();
+
+        for i in 0..LOG_CAPACITY + 5 {
+            buffer.push(record(&i.to_string()));
+        }
+
+        let records = buffer.records();
+        assert_eq!(records.len(), LOG_CAPACITY);
+        assert_eq!(records[0].message, "5");
+        assert_eq!(records[LOG_CAPACITY - 1].message, (LOG_CAPACITY + 4).to_string());
+
+        buffer.clear();
+        assert!(buffer.records().is_empty());
+    }
+
+    #[test]
+    fn buffer_layer_records_events_with_their_spans() {
+        let buffer = LogBuffer::default();
+        let subscriber = tracing_subscriber::registry().with(BufferLayer { buffer: buffer.clone() });
+
+        tracing::subscriber::with_default(subscriber, || {
+            let _connect = tracing::info_span!("connect", ssid = "Lab").entered();
+            let _sample = tracing::info_span!("sample", interface = "wlan0").entered();
+            tracing::warn!(signal = -70, "Weak signal");
+        });
+
+        let records = buffer.records();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].level, Level::WARN);
+        assert_eq!(records[0].spans, "connect{ssid=Lab}:sample{interface=wlan0}");
+        assert_eq!(records[0].message, "Weak signal signal=-70");
+    }
+}
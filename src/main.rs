@@ -1,9 +1,14 @@
+#![cfg_attr(not(debug_assertions), windows_subsystem = "windows")] // hide console window on Windows in release
+
 use eframe::{run_native, NativeOptions};
 use egui::IconData;
 
+use triangle_gator::logging;
 use triangle_gator::TriangleGator;
 
 fn main() -> Result<(), eframe::Error> {
+    let logging = logging::init(logging::DEFAULT_FILTER);
+
     let icon_image = image::open("assets/narly.png").expect("Should be able to open icon PNG file");
     let width = icon_image.width();
     let height = icon_image.height();
@@ -16,8 +21,9 @@ fn main() -> Result<(), eframe::Error> {
         
     let options = NativeOptions {
         viewport: egui::ViewportBuilder::default()
-        .with_inner_size([330.0, 400.0])
-        .with_resizable(false)
+        .with_inner_size([1100.0, 720.0]) // Restored from the last run once eframe has saved it
+        .with_min_inner_size([640.0, 420.0])
+        .with_resizable(true)
         .with_decorations(false)
         .with_transparent(true)
         .with_icon(icon_data),
@@ -27,8 +33,8 @@ fn main() -> Result<(), eframe::Error> {
     run_native(
         "Triangle Gator", 
         options, 
-        Box::new(|_cc| {
-            Ok(Box::new(TriangleGator::default()))
+        Box::new(|cc| {
+            Ok(Box::new(TriangleGator::new(cc, logging)))
         }) 
     )
 }
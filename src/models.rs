@@ -0,0 +1,99 @@
+use serde::{Deserialize, Serialize};
+
+// One reading of the current link, as reported by the wireless driver
+#[derive(Clone, Copy)]
+pub struct LinkReading {
+    pub signal_level: f32, // dBm
+    pub tx_power: f32, // dBm
+}
+
+#[derive(Clone, Serialize, Deserialize)] 
+pub struct NetInfo {
+    pub tx_power: Option<f32>,
+    pub measured_power: Option<f32>,
+    #[serde(default)]
+    pub samples: Vec<f32>, // Individual RSSI readings the measured power was averaged from
+}
+
+impl NetInfo {
+    // Averages a run of link readings into one measurement
+    pub fn from_readings(readings: &[LinkReading]) -> NetInfo {
+        let samples: Vec<f32> = readings.iter().map(|reading| reading.signal_level).collect();
+
+        if readings.is_empty() {
+            return NetInfo { measured_power: None, tx_power: None, samples };
+        }
+
+        let count = readings.len() as f32;
+        let measured_power = samples.iter().sum::<f32>() / count;
+        let tx_power = readings.iter().map(|reading| reading.tx_power).sum::<f32>() / count;
+
+        return NetInfo { measured_power: Some(measured_power), tx_power: Some(tx_power), samples };
+    }
+
+    pub fn rssi_min(&self) -> Option<f32> {
+        return self.samples.iter().cloned().reduce(f32::min);
+    }
+
+    pub fn rssi_max(&self) -> Option<f32> {
+        return self.samples.iter().cloned().reduce(f32::max);
+    }
+
+    pub fn rssi_std_dev(&self) -> Option<f32> {
+        if self.samples.is_empty() {
+            return None;
+        }
+
+        let count = self.samples.len() as f32;
+        let mean = self.samples.iter().sum::<f32>() / count;
+        let variance = self.samples.iter().map(|sample| (sample - mean).powi(2)).sum::<f32>() / count;
+
+        return Some(variance.sqrt());
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)] 
+pub struct Point {
+    pub x: f32,
+    pub y: f32,
+    #[serde(default)]
+    pub z: f32, // Height above the network, taken out of the range before solving
+    pub net_info: Option<NetInfo>,
+    #[serde(default)]
+    pub excluded: bool, // Left out of the solve, e.g. a reading taken next to a microwave
+}
+
+impl Point {
+    pub fn new(x: f32, y: f32, net_info: Option<NetInfo>) -> Point {
+        return Point { x, y, z: 0.0, net_info, excluded: false };
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)] 
+pub struct Location {
+    pub x: f32,
+    pub y: f32,
+    pub uncertainty: f32, // RMS of the range residuals, in plot units
+}
+
+// A wall on the floor plan, costing signals that pass through it some dB
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Wall {
+    pub start: [f32; 2],
+    pub end: [f32; 2],
+    pub attenuation_db: f32,
+}
+
+impl Wall {
+    // Whether the straight path between two positions passes through the wall
+    pub fn crosses(&self, from: [f32; 2], to: [f32; 2]) -> bool {
+        let orientation = |a: [f32; 2], b: [f32; 2], c: [f32; 2]| (b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0]);
+
+        let d1 = orientation(self.start, self.end, from);
+        let d2 = orientation(self.start, self.end, to);
+        let d3 = orientation(from, to, self.start);
+        let d4 = orientation(from, to, self.end);
+
+        return d1 * d2 < 0.0 && d3 * d4 < 0.0;
+    }
+}
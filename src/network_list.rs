@@ -0,0 +1,243 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::network_manager::Network;
+use crate::security::SecurityKind;
+
+// Parts of an SSID that suggest a managed network, which would rarely be left open
+const CORPORATE_WORDS: [&str; 10] = ["corp", "staff", "employee", "internal", "office", "secure", "enterprise", "admin", "finance", "company"];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Band {
+    TwoPointFour,
+    Five,
+    Six,
+}
+
+impl Band {
+    // Frequency in MHz, None outside the Wi-Fi bands
+    pub fn from_frequency(frequency: u32) -> Option<Band> {
+        return match frequency {
+            2400..=2500 => Some(Band::TwoPointFour),
+            5150..=5900 => Some(Band::Five),
+            5925..=7125 => Some(Band::Six),
+            _ => None,
+        };
+    }
+}
+
+impl fmt::Display for Band {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            Band::TwoPointFour => write!(f, "2.4 GHz"),
+            Band::Five => write!(f, "5 GHz"),
+            Band::Six => write!(f, "6 GHz"),
+        };
+    }
+}
+
+// What the network list can be sorted by
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum NetworkColumn {
+    Ssid,
+    #[default]
+    Signal,
+    Security,
+    Band,
+    Bssids,
+}
+
+impl NetworkColumn {
+    pub const ALL: [NetworkColumn; 5] = [
+        NetworkColumn::Signal,
+        NetworkColumn::Security,
+        NetworkColumn::Ssid,
+        NetworkColumn::Band,
+        NetworkColumn::Bssids,
+    ];
+
+    // Which way a first click sorts, strongest and busiest first
+    pub fn default_ascending(&self) -> bool {
+        return matches!(self, NetworkColumn::Ssid | NetworkColumn::Security);
+    }
+}
+
+impl fmt::Display for NetworkColumn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            NetworkColumn::Ssid => write!(f, "SSID"),
+            NetworkColumn::Signal => write!(f, "Signal"),
+            NetworkColumn::Security => write!(f, "Security"),
+            NetworkColumn::Band => write!(f, "Band"),
+            NetworkColumn::Bssids => write!(f, "APs"),
+        };
+    }
+}
+
+// nmcli's signal quality back to dBm. NetworkManager maps -90..-20 dBm onto 30..100%, so anything
+// weaker than 30% is only a guess.
+pub fn percent_to_dbm(percent: u32) -> i32 {
+    return percent.min(100) as i32 - 120;
+}
+
+// Filled bars out of four
+pub fn signal_bars(percent: u32) -> usize {
+    return match percent {
+        75.. => 4,
+        50..=74 => 3,
+        25..=49 => 2,
+        1..=24 => 1,
+        0 => 0,
+    };
+}
+
+// Reasons to look twice before trusting the network, empty if there aren't any
+pub fn suspicions(network: &Network) -> Vec<&'static str> {
+    let mut suspicions = Vec::new();
+
+    if network.mixed_security {
+        suspicions.push("The same SSID is advertised with different security, which could be an evil twin");
+    }
+
+    let ssid = network.ssid.to_lowercase();
+    if network.kind == SecurityKind::Open && CORPORATE_WORDS.iter().any(|word| ssid.contains(word)) {
+        suspicions.push("Open network with a corporate-looking SSID");
+    }
+
+    if network.kind == SecurityKind::Wep {
+        suspicions.push("WEP can be cracked in minutes");
+    }
+
+    return suspicions;
+}
+
+// Case insensitive match against the SSID, security, band and BSSIDs
+pub fn matches_filter(network: &Network, filter: &str) -> bool {
+    let filter = filter.trim().to_lowercase();
+    if filter.is_empty() {
+        return true;
+    }
+
+    let band = network.band().map(|band| band.to_string()).unwrap_or_default();
+
+    return network.ssid.to_lowercase().contains(&filter)
+        || network.kind.to_string().to_lowercase().contains(&filter)
+        || band.to_lowercase().contains(&filter)
+        || network.bssids.iter().any(|bssid| bssid.to_lowercase().contains(&filter));
+}
+
+// Ties fall back to the SSID, A to Z whichever way the column goes, so the order doesn't jump around between scans
+pub fn sort(networks: &mut [&Network], column: NetworkColumn, ascending: bool) {
+    networks.sort_by(|a, b| {
+        let ssids = a.ssid.to_lowercase().cmp(&b.ssid.to_lowercase());
+        let ordering = match column {
+            NetworkColumn::Ssid => ssids,
+            NetworkColumn::Signal => a.measured_power.cmp(&b.measured_power),
+            NetworkColumn::Security => security_rank(a.kind).cmp(&security_rank(b.kind)),
+            NetworkColumn::Band => a.frequency.cmp(&b.frequency),
+            NetworkColumn::Bssids => a.bssids.len().cmp(&b.bssids.len()),
+        };
+        let ordering = if ascending { ordering } else { ordering.reverse() };
+
+        return ordering.then(ssids);
+    });
+}
+
+fn security_rank(kind: SecurityKind) -> usize {
+    return SecurityKind::ALL.iter().position(|other| *other == kind).unwrap_or(0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn network(ssid: &str, signal: u32, security: &str, frequency: u32, bssids: usize) -> Network {
+        let mut network = Network::new(String::from(ssid), signal, Some(String::from(security)));
+        network.frequency = Some(frequency);
+        network.bssids = (0..bssids).map(|i| format!("AA:BB:CC:DD:EE:{:02X}", i)).collect();
+
+        return network;
+    }
+
+    fn networks() -> Vec<Network> {
+        return vec![
+            network("cafe", 60, "WPA2", 2437, 1),
+            network("Attic", 80, "WPA2 802.1X", 5180, 3),
+            network("Basement", 60, "--", 5955, 1),
+            network("Den", 40, "WEP", 2412, 2),
+        ];
+    }
+
+    #[test]
+    fn sorts_by_each_column() {
+        let networks = networks();
+
+        let cases = [
+            (NetworkColumn::Signal, false, ["Attic", "Basement", "cafe", "Den"]), // Ties stay A to Z
+            (NetworkColumn::Signal, true, ["Den", "Basement", "cafe", "Attic"]),
+            (NetworkColumn::Ssid, true, ["Attic", "Basement", "cafe", "Den"]),
+            (NetworkColumn::Ssid, false, ["Den", "cafe", "Basement", "Attic"]),
+            (NetworkColumn::Security, true, ["Basement", "Den", "cafe", "Attic"]),
+            (NetworkColumn::Band, false, ["Basement", "Attic", "cafe", "Den"]),
+            (NetworkColumn::Bssids, false, ["Attic", "Den", "Basement", "cafe"]),
+        ];
+
+        for (column, ascending, expected) in cases {
+            let mut sorted: Vec<&Network> = networks.iter().collect();
+            sort(&mut sorted, column, ascending);
+
+            let ssids: Vec<&str> = sorted.iter().map(|network| network.ssid.as_str()).collect();
+            assert_eq!(ssids, expected, "{} ascending {}", column, ascending);
+        }
+    }
+
+    #[test]
+    fn filters() {
+        let networks = networks();
+
+        let cases = [
+            ("", vec!["cafe", "Attic", "Basement", "Den"]),
+            ("   ", vec!["cafe", "Attic", "Basement", "Den"]),
+            ("CAFE", vec!["cafe"]),
+            ("802.1x", vec!["Attic"]),
+            ("wep", vec!["Den"]),
+            ("5 ghz", vec!["Attic"]),
+            ("2.4", vec!["cafe", "Den"]),
+            ("ee:02", vec!["Attic"]),
+            ("nowhere", vec![]),
+        ];
+
+        for (filter, expected) in cases {
+            let ssids: Vec<&str> = networks.iter().filter(|network| matches_filter(network, filter)).map(|network| network.ssid.as_str()).collect();
+            assert_eq!(ssids, expected, "{:?}", filter);
+        }
+    }
+
+    #[test]
+    fn flags_suspicious_networks() {
+        let mut twin = network("Home", 70, "WPA2", 2437, 2);
+        twin.mixed_security = true;
+
+        let cases = [
+            (network("Home", 70, "WPA2", 2437, 1), 0),
+            (twin, 1),
+            (network("Staff-WiFi", 70, "--", 2437, 1), 1),
+            (network("Staff-WiFi", 70, "WPA2", 2437, 1), 0),
+            (network("Old", 70, "WEP", 2437, 1), 1),
+        ];
+
+        for (network, count) in cases {
+            assert_eq!(suspicions(&network).len(), count, "{}", network.ssid);
+        }
+    }
+
+    #[test]
+    fn converts_signal() {
+        assert_eq!(percent_to_dbm(100), -20);
+        assert_eq!(percent_to_dbm(30), -90);
+        assert_eq!(percent_to_dbm(150), -20);
+        assert_eq!([0, 10, 30, 60, 90].map(signal_bars), [0, 1, 2, 3, 4]);
+        assert_eq!([2412, 5180, 5955, 60000].map(Band::from_frequency), [Some(Band::TwoPointFour), Some(Band::Five), Some(Band::Six), None]);
+    }
+}
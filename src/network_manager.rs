@@ -1,31 +1,80 @@
-use std::process::Command;
-use std::str;
+use std::env;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::process::{self, Command, Output, Stdio};
 
 use std::{thread, time};
 
-use crate::trilateration_calc::{NetInfo, Point};
+use serde::Serialize;
+use tracing::{debug, error, info, info_span, warn};
+use zeroize::Zeroizing;
+
+use crate::device_profile::{DeviceProfile, DeviceProfiles};
+use crate::interfaces::{self, WirelessInterface};
+pub use crate::models::LinkReading;
+use crate::models::{NetInfo, Point};
+use crate::network_list::Band;
+use crate::security::{Credentials, SecurityKind};
+use crate::trilateration_calc::TrilaterationCalculator;
+
+const PROFILE_PREFIX: &str = "triangle-gator-"; // Connection profiles we create are named this, then the SSID
+pub const BACKEND_TOOLS: [&str; 2] = ["nmcli", "iwconfig"]; // Commands the survey can't run without
+
+// Why a backend command didn't do what we asked
+#[derive(Debug)]
+pub enum BackendError {
+    MissingTool(&'static str), // Not installed, or not on PATH
+    Io(io::Error),
+    Failed(&'static str, String), // The tool ran and refused, with what it said
+    NoNetworkSelected,
+}
+
+impl fmt::Display for BackendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            BackendError::MissingTool(tool) => write!(f, "{} not found, is it installed?", tool),
+            BackendError::Io(e) => write!(f, "{}", e),
+            BackendError::Failed(tool, e) => write!(f, "{}: {}", tool, e),
+            BackendError::NoNetworkSelected => write!(f, "no network selected"),
+        };
+    }
+}
+
+impl std::error::Error for BackendError {}
+
+impl From<io::Error> for BackendError {
+    fn from(e: io::Error) -> Self {
+        return BackendError::Io(e);
+    }
+}
 
+#[derive(Default)]
 pub struct NetworkManager {
     available_networks: Vec<Network>, // Store networks in a vector
     selected_network: Option<Network>, // Store the currently selected network REPLACE WITH NETWORK STRUCT
 
     connected: bool, // Wether or not the user is currently connected to the desired network
-}
 
-impl Default for NetworkManager {
-    fn default() -> Self {
-        Self {
-            available_networks: Vec::new(),
-            selected_network: None,
+    interface: Option<String>, // Wireless interface to scan and measure on, the backends' default if None
+    device_profile: Option<DeviceProfile>, // Correction for this machine's adapter, applied to every reading
+    temporary_profile: Option<String>, // nmcli connection profile created to join the selected network
+    original_connection: Option<ActiveConnection>, // What the adapter was connected to before we switched it
+}
 
-            connected: false,
-        }
-    }
+// A connection NetworkManager has up, as `nmcli connection show --active` lists it
+#[derive(Clone)]
+pub struct ActiveConnection {
+    pub uuid: String,
+    pub name: String,
+    pub device: String,
 }
 
 impl NetworkManager {
-    pub fn ready_to_calc(&self, points: &[Point; 3]) -> bool {
-        return self.get_selected_network().is_some() && points.iter().all(|point| point.net_info.is_some());
+    // Counts the points the solver will actually use
+    pub fn ready_to_calc(&self, points: &[Point], calc: &TrilaterationCalculator) -> bool {
+        return self.get_selected_network().is_some() && points.iter().filter(|point| calc.is_usable(point)).count() >= 3;
     }
 
     pub fn get_available_networks(&self) -> &Vec<Network> {
@@ -40,8 +89,8 @@ impl NetworkManager {
         return &self.selected_network;
     }
 
-    pub fn select_network(&mut self, network: Option<&Network>) {
-        self.selected_network = Some(Network::from(network.unwrap()));
+    pub fn select_network(&mut self, network: &Network) {
+        self.selected_network = Some(Network::from(network));
     }
 
     pub fn get_connection_status(&self) -> bool {
@@ -52,170 +101,611 @@ impl NetworkManager {
         self.connected = connected;
     }
 
+    pub fn get_interface(&self) -> Option<&str> {
+        return self.interface.as_deref();
+    }
+
+    // Switches adapter, picking up its device profile with it
+    pub fn set_interface(&mut self, interface: Option<String>) {
+        self.interface = interface;
+        self.load_device_profile();
+    }
+
+    // The chosen interface, or the first wireless one if none was chosen
+    pub fn current_interface(&self) -> Option<WirelessInterface> {
+        return match &self.interface {
+            Some(interface) => Some(WirelessInterface::of(interface)),
+            None => interfaces::wireless_interfaces().into_iter().next(),
+        };
+    }
+
+    pub fn get_device_profile(&self) -> Option<&DeviceProfile> {
+        return self.device_profile.as_ref();
+    }
+
+    pub fn set_device_profile(&mut self, device_profile: Option<DeviceProfile>) {
+        self.device_profile = device_profile;
+    }
+
+    // Picks up the saved profile for the wireless adapter, if one has been calibrated
+    pub fn load_device_profile(&mut self) {
+        let Some(interface) = self.current_interface() else {
+            self.device_profile = None;
+            return;
+        };
+
+        match DeviceProfiles::load() {
+            Ok(profiles) => self.device_profile = profiles.find(&interface),
+            Err(e) => warn!("Failed to load device profiles: {}", e),
+        }
+    }
+
     pub fn reset_network_manager(&mut self) {
-        self.disconnect_from_network();
-        self.is_connected(false);
+        self.restore_connection();
         self.selected_network = None;
     }
 
-    pub fn connect_to_network(&mut self, password: String) -> bool {
-        let ssid = &self.get_selected_network().as_ref().unwrap().ssid;
+    pub fn get_original_connection(&self) -> Option<&ActiveConnection> {
+        return self.original_connection.as_ref();
+    }
+
+    // Leaves the surveyed network, removes the profiles we made for it, and puts the adapter back on
+    // whatever it was connected to before. Does nothing if we never switched networks.
+    pub fn restore_connection(&mut self) {
+        let _span = info_span!("restore").entered();
 
-        println!("Connecting to '{}' with password: {}", ssid, password);
+        if self.connected {
+            self.disconnect_from_network();
+            self.is_connected(false);
+        }
 
-        let output = Command::new("nmcli")
+        if let Some(profile) = self.temporary_profile.take() {
+            delete_profile(&profile);
+        }
+
+        if let Some(original) = self.original_connection.take() {
+            let output = Command::new("nmcli")
+                .args(["connection", "up", "uuid", &original.uuid, "ifname", &original.device])
+                .output();
+
+            match output {
+                Ok(output) if output.status.success() => info!("Reconnected to '{}'", original.name),
+                Ok(output) => warn!("Failed to reconnect to '{}': {}", original.name, String::from_utf8_lossy(&output.stderr)),
+                Err(e) => error!("Error executing nmcli: {}", e),
+            }
+        }
+    }
+
+    // Hidden and 802.1X networks need a connection profile, everything else nmcli can join directly
+    pub fn connect_to_network(&mut self, credentials: &Credentials) -> Result<(), BackendError> {
+        let network = self.get_selected_network().as_ref().map(Network::from).ok_or(BackendError::NoNetworkSelected)?;
+
+        // Only the first switch counts, later ones would record our own survey network
+        if self.original_connection.is_none() && self.temporary_profile.is_none() {
+            self.original_connection = active_connection(self.get_interface());
+        }
+
+        let _span = info_span!("connect", ssid = %network.ssid, kind = %network.kind, hidden = network.hidden).entered();
+        info!("Connecting to '{}'", network.ssid);
+
+        if network.hidden || network.kind == SecurityKind::Enterprise {
+            return self.connect_with_profile(credentials);
+        }
+
+        return self.connect_directly(credentials.password());
+    }
+
+    // The password goes to nmcli's --ask prompt over stdin, so it never shows up in ps or the logs
+    fn connect_directly(&mut self, password: &str) -> Result<(), BackendError> {
+        let ssid = self.get_selected_network().as_ref().ok_or(BackendError::NoNetworkSelected)?.ssid.clone();
+
+        // nmcli saves a profile named after the SSID when it joins a new network, which is ours to
+        // clean up. One that was already there is the user's.
+        let had_profile = profile_names().contains(&ssid);
+
+        let child = Command::new("nmcli")
+            .arg("--ask")
             .arg("dev")
             .arg("wifi")
             .arg("connect")
-            .arg(ssid)
-            .arg("password")
-            .arg(password)
-            .output();
+            .arg(&ssid)
+            .args(ifname_args(self.get_interface()))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn();
+
+        let output = child.and_then(|mut child| {
+            let mut stdin = child.stdin.take().unwrap();
+
+            if !password.is_empty() {
+                let line = Zeroizing::new(format!("{}\n", password));
+                // An open network never asks, so a closed pipe here is fine
+                let _ = stdin.write_all(line.as_bytes());
+            }
+            drop(stdin);
 
-        match output {
-            Ok(output) => {
-                if output.status.success() {
-                    println!("Successfully connected to '{}'", ssid);
-                } else {
-                    eprintln!(
-                        "Failed to connect: {}",
-                        String::from_utf8_lossy(&output.stderr)
-                    );
-                    return false;
+            child.wait_with_output()
+        });
+
+        check_output("nmcli", output)?;
+        info!("Successfully connected to '{}'", ssid);
+
+        if !had_profile {
+            self.temporary_profile = Some(ssid);
+        }
+
+        return Ok(());
+    }
+
+    // Creates a profile for the selected network and brings it up. Secrets go in a passwd-file
+    // readable only by us, and aren't saved in the profile.
+    fn connect_with_profile(&mut self, credentials: &Credentials) -> Result<(), BackendError> {
+        let network = self.get_selected_network().as_ref().ok_or(BackendError::NoNetworkSelected)?;
+        let ssid = network.ssid.clone();
+        let kind = network.kind;
+        let profile = format!("{}{}", PROFILE_PREFIX, ssid);
+
+        // A leftover from an earlier run would make the add below fail
+        let _ = Command::new("nmcli").args(["connection", "delete", "id", &profile]).output();
+
+        let mut args: Vec<String> = ["connection", "add", "type", "wifi", "con-name", &profile, "ssid", &ssid, "ifname", self.get_interface().unwrap_or("*")]
+            .iter().map(|arg| String::from(*arg)).collect();
+
+        if network.hidden {
+            args.extend([String::from("802-11-wireless.hidden"), String::from("yes")]);
+        }
+
+        if let Some(key_mgmt) = kind.key_mgmt() {
+            args.extend([String::from("wifi-sec.key-mgmt"), String::from(key_mgmt)]);
+        }
+
+        match (kind, credentials) {
+            (SecurityKind::Wep, _) => args.extend(["wifi-sec.wep-key-type", "1", "wifi-sec.wep-key-flags", "2"].map(String::from)),
+            (SecurityKind::WpaPsk | SecurityKind::Sae, _) => args.extend(["wifi-sec.psk-flags", "2"].map(String::from)),
+            (SecurityKind::Enterprise, Credentials::Enterprise(enterprise)) => {
+                args.extend([
+                    String::from("802-1x.eap"), String::from(enterprise.eap.nmcli_name()),
+                    String::from("802-1x.phase2-auth"), String::from(enterprise.phase2.nmcli_name()),
+                    String::from("802-1x.identity"), enterprise.identity.clone(),
+                    String::from("802-1x.password-flags"), String::from("2"),
+                ]);
+
+                if !enterprise.anonymous_identity.is_empty() {
+                    args.extend([String::from("802-1x.anonymous-identity"), enterprise.anonymous_identity.clone()]);
+                }
+                if let Some(ca_cert) = &enterprise.ca_cert {
+                    args.extend([String::from("802-1x.ca-cert"), ca_cert.to_string_lossy().into_owned()]);
                 }
             }
-            Err(e) => eprintln!("Error executing nmcli: {}", e),
+            _ => {}
+        }
+
+        check_output("nmcli", Command::new("nmcli").args(&args).output())?;
+        self.temporary_profile = Some(profile.clone());
+
+        let mut up = Command::new("nmcli");
+        up.args(["connection", "up", "id", &profile]);
+
+        // Kept alive until nmcli is done with it
+        let passwd_file = match kind.password_setting() {
+            Some(setting) => Some(PasswdFile::create(setting, credentials.password())?),
+            None => None,
+        };
+
+        if let Some(passwd_file) = &passwd_file {
+            up.arg("passwd-file").arg(&passwd_file.path);
         }
 
-        return true;
+        check_output("nmcli", up.output())?;
+        info!("Successfully connected to '{}'", ssid);
+
+        return Ok(());
     }
 
     pub fn disconnect_from_network(&mut self) {
-        let ssid = &self.get_selected_network().as_ref().unwrap().ssid;
+        let Some(network) = self.get_selected_network() else {
+            return;
+        };
+
+        // The profile we made, or the user's own one for this SSID
+        let profile = self.temporary_profile.as_deref().unwrap_or(&network.ssid);
 
         let output = Command::new("nmcli")
             .arg("connection")
             .arg("down")
-            .arg(ssid)
-            .output()
-            .expect("Failed to execute nmcli");
+            .arg("id")
+            .arg(profile)
+            .output();
 
-        
-        if output.status.success() {
-            println!("Successfully disconnected from '{}'", ssid);
-        } else {
-            eprintln!(
-                "Failed to disconnect: {}",
-                String::from_utf8_lossy(&output.stderr)
-            );
+        match output {
+            Ok(output) if output.status.success() => info!("Successfully disconnected from '{}'", network.ssid),
+            Ok(output) => warn!("Failed to disconnect: {}", String::from_utf8_lossy(&output.stderr)),
+            Err(e) => error!("Error executing nmcli: {}", e),
         }
     }
 
-    pub fn scan_networks(&mut self) {
+    pub fn scan_networks(&mut self) -> Result<(), BackendError> {
         if self.get_selected_network().is_none() {
+            let _span = info_span!("scan", interface = self.get_interface()).entered();
+
             let output = Command::new("nmcli")
-            .args(&["-t", "-f", "SSID, SIGNAL, SECURITY", "dev", "wifi", "list"]) // maybe add , "list"
-            .output()
-            .expect("Failed to execute nmcli");
-    
-            if output.status.success() {
-                self.clear_available_networks();
-                let networks = String::from_utf8_lossy(&output.stdout);
-                if networks.trim().is_empty() {
-                    print!("Could not find any networks");
-                } else {
-                    for network in networks.lines() {
-                        const NUM_OF_ARGS: usize = 3;
-                        let mut parts = network.splitn(NUM_OF_ARGS, ':'); // Split SSID and SIGNAL at the colon
-                        if let (Some(ssid), Some(signal), Some(security)) = (parts.next(), parts.next(), parts.next()) {
-                            if ssid != "" {
-    
-                                let mut sec: Option<String> = None;
-    
-                                if security != "" {
-                                    sec = Some(security.parse().unwrap());
-                                }
-    
-                                let network = Network::new(ssid.parse().unwrap(), signal.parse().unwrap(), sec);
-    
-                                self.available_networks.push(network);
-                                
-                            }
-                        }
+            .args(["-t", "-f", "SSID,BSSID,SIGNAL,SECURITY,CHAN,FREQ", "dev", "wifi", "list"])
+            .args(ifname_args(self.get_interface()))
+            .output();
+
+            let output = check_output("nmcli", output)?;
+
+            self.clear_available_networks();
+            let networks = String::from_utf8_lossy(&output.stdout);
+            if networks.trim().is_empty() {
+                debug!("Could not find any networks");
+            } else {
+                // One line per access point, grouped by SSID
+                for network in networks.lines() {
+                    let fields = split_terse(network);
+                    let [ssid, bssid, signal, security, channel, frequency] = fields.as_slice() else {
+                        debug!("Skipping unreadable scan line: {}", network);
+                        continue;
+                    };
+                    let Ok(signal) = signal.parse() else {
+                        debug!("Skipping unreadable scan line: {}", network);
+                        continue;
+                    };
+
+                    if ssid.is_empty() {
+                        continue;
+                    }
+
+                    let security = if security.is_empty() { None } else { Some(security.clone()) };
+                    let mut access_point = Network::new(ssid.clone(), signal, security);
+                    access_point.bssids = vec![bssid.clone()];
+                    access_point.channel = channel.parse().ok();
+                    access_point.frequency = frequency.split_whitespace().next().and_then(|frequency| frequency.parse().ok()); // e.g. "2437 MHz"
+
+                    match self.available_networks.iter_mut().find(|network| network.ssid == access_point.ssid) {
+                        Some(network) => network.add_access_point(access_point),
+                        None => self.available_networks.push(access_point),
                     }
                 }
-            } else {
-                eprintln!("Error running nmcli: {}", String::from_utf8_lossy(&output.stderr));
             }
         }
+
+        return Ok(());
     }
 
     pub fn ping_network(&self, sample_scale: u16, sample_length: u64) -> NetInfo {
         // return NetInfo { measured_power: Some(-38.0), tx_power: Some(15.0) };
-        // Execute the nmcli command to get both Tx Power and Signal Level
 
-        let sample_length = time::Duration::from_millis(sample_length);
+        let mut readings = sample_link(self.get_interface(), sample_scale, sample_length);
+
+        if let Some(device_profile) = &self.device_profile {
+            readings = readings.into_iter().map(|reading| device_profile.correct(reading)).collect();
+        }
+
+        return NetInfo::from_readings(&readings);
+    }
+}
+
+// Leaves the machine as we found it if the app is closed or crashes mid-survey
+impl Drop for NetworkManager {
+    fn drop(&mut self) {
+        self.restore_connection();
+    }
+}
+
+// The Wi-Fi connection up on the interface, or on any interface if none was chosen
+pub fn active_connection(interface: Option<&str>) -> Option<ActiveConnection> {
+    let output = Command::new("nmcli")
+        .args(["-t", "-f", "UUID,TYPE,DEVICE,NAME", "connection", "show", "--active"])
+        .output()
+        .ok()?;
+
+    let connections = String::from_utf8_lossy(&output.stdout);
+
+    for line in connections.lines() {
+        // NAME goes last since it's the only field that can contain an (escaped) colon
+        let mut parts = line.splitn(4, ':');
+        let (Some(uuid), Some(kind), Some(device), Some(name)) = (parts.next(), parts.next(), parts.next(), parts.next()) else {
+            continue;
+        };
+
+        if kind != "802-11-wireless" || interface.is_some_and(|interface| interface != device) {
+            continue;
+        }
+
+        return Some(ActiveConnection { uuid: String::from(uuid), name: unescape(name), device: String::from(device) });
+    }
+
+    return None;
+}
+
+// Every saved connection profile's name
+fn profile_names() -> Vec<String> {
+    let Ok(output) = Command::new("nmcli").args(["-t", "-f", "NAME", "connection", "show"]).output() else {
+        return vec![];
+    };
+
+    return String::from_utf8_lossy(&output.stdout).lines().map(unescape).collect();
+}
+
+fn delete_profile(profile: &str) {
+    match Command::new("nmcli").args(["connection", "delete", "id", profile]).output() {
+        Ok(output) if output.status.success() => info!("Removed connection profile '{}'", profile),
+        Ok(output) => warn!("Failed to remove connection profile '{}': {}", profile, String::from_utf8_lossy(&output.stderr)),
+        Err(e) => error!("Error executing nmcli: {}", e),
+    }
+}
+
+// nmcli's terse output escapes colons and backslashes in values
+fn unescape(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unescaped.extend(chars.next()),
+            _ => unescaped.push(c),
+        }
+    }
+
+    return unescaped;
+}
+
+// Splits a line of nmcli's terse output on the colons it didn't escape, unescaping each field
+fn split_terse(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => fields.last_mut().unwrap().extend(chars.next()),
+            ':' => fields.push(String::new()),
+            _ => fields.last_mut().unwrap().push(c),
+        }
+    }
+
+    return fields;
+}
+
+// Turns a command that couldn't start, or that exited with an error, into a BackendError
+fn check_output(tool: &'static str, output: io::Result<Output>) -> Result<Output, BackendError> {
+    let output = match output {
+        Ok(output) => output,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Err(BackendError::MissingTool(tool)),
+        Err(e) => return Err(BackendError::Io(e)),
+    };
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(BackendError::Failed(tool, if stderr.is_empty() { output.status.to_string() } else { stderr }));
+    }
+
+    return Ok(output);
+}
+
+// Whether a command is on PATH, without running it
+pub fn find_tool(tool: &str) -> Option<PathBuf> {
+    let path = env::var_os("PATH")?;
+
+    return env::split_paths(&path).map(|dir| dir.join(tool)).find(|candidate| candidate.is_file());
+}
+
+// Backend commands that aren't installed
+pub fn missing_tools() -> Vec<&'static str> {
+    return BACKEND_TOOLS.into_iter().filter(|tool| find_tool(tool).is_none()).collect();
+}
+
+// `setting:secret` file for nmcli's passwd-file, wiped and removed when dropped
+struct PasswdFile {
+    path: PathBuf,
+}
 
-        let mut signal_strength = 0.0;
-        let mut tx_power = 0.0;
-        
-        for _ in 0..sample_scale {
+impl PasswdFile {
+    fn create(setting: &str, secret: &str) -> io::Result<PasswdFile> {
+        let path = std::env::temp_dir().join(format!("{}{}.passwd", PROFILE_PREFIX, process::id()));
+        let contents = Zeroizing::new(format!("{}:{}\n", setting, secret));
 
-            let output = Command::new("iwconfig")
-                .output()
-                .expect("Failed to execute iwconfig");
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+        // Clear out one left behind by a crash, it can't be trusted to be ours
+        let _ = fs::remove_file(&path);
+        options.open(&path)?.write_all(contents.as_bytes())?;
+
+        return Ok(PasswdFile { path });
+    }
+}
 
-            if !output.status.success() {
-                println!("Failed to execute iwconfig");
+impl Drop for PasswdFile {
+    fn drop(&mut self) {
+        if let Ok(metadata) = fs::metadata(&self.path) {
+            if let Ok(mut file) = File::options().write(true).open(&self.path) {
+                let _ = file.write_all(&vec![0; metadata.len() as usize]);
             }
+        }
+
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+// nmcli arguments restricting a command to one interface
+fn ifname_args(interface: Option<&str>) -> Vec<&str> {
+    return interface.map(|interface| vec!["ifname", interface]).unwrap_or_default();
+}
+
+// Raw readings of the current link, sample_length ms apart
+pub fn sample_link(interface: Option<&str>, sample_scale: u16, sample_length: u64) -> Vec<LinkReading> {
+    let _span = info_span!("sample", interface, samples = sample_scale).entered();
+
+    let sample_length = time::Duration::from_millis(sample_length);
+
+    let mut readings = Vec::new();
+    
+    for _ in 0..sample_scale {
+        if let Some(reading) = read_link(interface) {
+            debug!(signal_level = reading.signal_level, tx_power = reading.tx_power, "Link reading");
+            readings.push(reading);
+        }
+
+        thread::sleep(sample_length);
+    }
+
+    return readings;
+}
+
+// Execute iwconfig to get both Tx Power and Signal Level of the current link, on one interface or whichever it lists first
+pub fn read_link(interface: Option<&str>) -> Option<LinkReading> {
+    let output = match Command::new("iwconfig").args(interface).output() {
+        Ok(output) => output,
+        Err(e) => {
+            error!("Failed to execute iwconfig: {}", e);
+            return None;
+        }
+    };
 
-            let output_str = str::from_utf8(&output.stdout).unwrap();
-            
-            for line in output_str.lines() {
-                if line.contains("Signal level=") {
-                    let parts: Vec<&str> = line.split_whitespace().collect();
-                    for part in parts {
-                        if part.starts_with("level=") {
-                            if let Some(value_str) = part.strip_prefix("level=") {
-                                if let Ok(value) = value_str.replace("dBm", "").parse::<f32>() {
-                                    signal_strength += value;
-                                }
-                            }
-                        }
+    if !output.status.success() {
+        warn!("iwconfig {}: {}", output.status, String::from_utf8_lossy(&output.stderr).trim());
+    }
+
+    let output_str = String::from_utf8_lossy(&output.stdout);
+
+    let mut signal_level = None;
+    let mut tx_power = None;
+
+    for line in output_str.lines() {
+        if line.contains("Signal level=") && signal_level.is_none() {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            for part in parts {
+                if let Some(value_str) = part.strip_prefix("level=") {
+                    if let Ok(value) = value_str.replace("dBm", "").parse::<f32>() {
+                        signal_level = Some(value);
                     }
                 }
-                if line.contains("Tx-Power=") {
-                    if let Some(start) = line.find("Tx-Power=") {
-                        let tx_power_part = &line[start..];
-                        let parts: Vec<&str> = tx_power_part.split_whitespace().collect();
-                        if parts.len() > 1 {
-                            if let Ok(value) = parts[0].split('=').nth(1).unwrap_or("0").parse::<f32>() {
-                                tx_power = value;
-                            }
-                        }
+            }
+        }
+        if line.contains("Tx-Power=") && tx_power.is_none() {
+            if let Some(start) = line.find("Tx-Power=") {
+                let tx_power_part = &line[start..];
+                let parts: Vec<&str> = tx_power_part.split_whitespace().collect();
+                if parts.len() > 1 {
+                    if let Ok(value) = parts[0].split('=').nth(1).unwrap_or("0").parse::<f32>() {
+                        tx_power = Some(value);
                     }
                 }
             }
-
-            thread::sleep(sample_length);
         }
-
-        return NetInfo { measured_power: Some(signal_strength / f32::from(sample_scale)), tx_power: Some(tx_power / f32::from(sample_scale)) };
     }
+
+    return Some(LinkReading { signal_level: signal_level?, tx_power: tx_power.unwrap_or(0.0) });
 }
 
+#[derive(Serialize)]
 pub struct Network {
     pub ssid: String,
-    pub measured_power: u32,
-    pub security: Option<String>
+    pub measured_power: u32, // Signal quality in percent, of the strongest access point
+    pub security: Option<String>, // As nmcli lists it
+    pub kind: SecurityKind,
+    pub hidden: bool, // Not broadcasting its SSID, so typed in rather than scanned
+    pub bssids: Vec<String>, // Every access point heard with this SSID
+    pub channel: Option<u32>, // Of the strongest access point
+    pub frequency: Option<u32>, // MHz, of the strongest access point
+    pub mixed_security: bool, // Some access points with this SSID advertise different security
 }
 
 impl Network {
     pub fn new(ssid: String, measured_power: u32, security: Option<String>) -> Network {
-        return Network { ssid, measured_power, security };
+        let kind = security.as_deref().map(SecurityKind::parse).unwrap_or_default();
+
+        return Network { ssid, measured_power, security, kind, hidden: false, bssids: Vec::new(), channel: None, frequency: None, mixed_security: false };
+    }
+
+    pub fn hidden(ssid: String, kind: SecurityKind) -> Network {
+        return Network { ssid, measured_power: 0, security: None, kind, hidden: true, bssids: Vec::new(), channel: None, frequency: None, mixed_security: false };
     }
 
     pub fn from(network: &Network) -> Network {
-        return Network { ssid: network.ssid.clone(), measured_power: network.measured_power, security: network.security.clone() };
+        return Network {
+            ssid: network.ssid.clone(),
+            measured_power: network.measured_power,
+            security: network.security.clone(),
+            kind: network.kind,
+            hidden: network.hidden,
+            bssids: network.bssids.clone(),
+            channel: network.channel,
+            frequency: network.frequency,
+            mixed_security: network.mixed_security,
+        };
+    }
+
+    pub fn band(&self) -> Option<Band> {
+        return self.frequency.and_then(Band::from_frequency);
+    }
+
+    // Folds in another access point with the same SSID, keeping the strongest one's signal and channel
+    fn add_access_point(&mut self, access_point: Network) {
+        if access_point.kind != self.kind {
+            self.mixed_security = true;
+        }
+
+        if access_point.measured_power > self.measured_power {
+            self.measured_power = access_point.measured_power;
+            self.security = access_point.security;
+            self.kind = access_point.kind;
+            self.channel = access_point.channel;
+            self.frequency = access_point.frequency;
+        }
+
+        self.bssids.extend(access_point.bssids);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_usable_points_count_towards_calculating() {
+        let calc = TrilaterationCalculator::default();
+        let reading = NetInfo { tx_power: Some(-30.0), measured_power: Some(-60.0), samples: Vec::new() };
+        let failed = NetInfo { tx_power: None, measured_power: None, samples: Vec::new() };
+
+        let mut points = vec![Point::new(0.0, 0.0, Some(reading.clone())), Point::new(10.0, 0.0, Some(reading.clone())), Point::new(0.0, 10.0, Some(failed))];
+
+        let mut network_manager = NetworkManager::default();
+        assert!(!network_manager.ready_to_calc(&points, &calc));
+
+        network_manager.select_network(&Network::new(String::from("Home"), 70, None));
+        assert!(!network_manager.ready_to_calc(&points, &calc));
+
+        points[2].net_info = Some(reading);
+        assert!(network_manager.ready_to_calc(&points, &calc));
+
+        points[2].excluded = true;
+        assert!(!network_manager.ready_to_calc(&points, &calc));
+    }
+
+    #[test]
+    fn connecting_needs_a_selected_network() {
+        let mut network_manager = NetworkManager::default();
+
+        let result = network_manager.connect_to_network(&Credentials::None);
+        assert!(matches!(result, Err(BackendError::NoNetworkSelected)));
+        assert!(network_manager.get_original_connection().is_none());
+    }
+
+    #[test]
+    fn passwd_files_are_private_and_removed() {
+        let passwd_file = PasswdFile::create("802-11-wireless-security.psk", "hunter22").unwrap();
+        let path = passwd_file.path.clone();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "802-11-wireless-security.psk:hunter22\n");
+        #[cfg(unix)]
+        assert_eq!(std::os::unix::fs::PermissionsExt::mode(&fs::metadata(&path).unwrap().permissions()) & 0o777, 0o600);
+
+        drop(passwd_file);
+        assert!(!path.exists());
     }
 }
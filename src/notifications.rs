@@ -0,0 +1,120 @@
+use std::mem;
+use std::time::{Duration, Instant};
+
+use tracing::{error, info, warn};
+
+const TOAST_DURATION: Duration = Duration::from_secs(4);
+const ERROR_TOAST_DURATION: Duration = Duration::from_secs(8); // Long enough to read a backend's error message
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Severity {
+    Success,
+    Info,
+    Error,
+}
+
+// A passing message, gone after a few seconds or when clicked
+pub struct Toast {
+    pub severity: Severity,
+    pub message: String,
+    pub shown: Instant,
+}
+
+impl Toast {
+    pub fn expires(&self) -> Instant {
+        return self.shown + if self.severity == Severity::Error { ERROR_TOAST_DURATION } else { TOAST_DURATION };
+    }
+}
+
+// Something that stops the survey going any further, shown until it's fixed
+#[derive(Clone, PartialEq)]
+pub enum Problem {
+    MissingTool(&'static str), // A backend command isn't installed
+    NoInterface,
+    ScanFailed(String), // nmcli ran but couldn't list networks, with what it said
+    SingularGeometry, // The tested points lie on a line, so there's no single solution
+}
+
+impl Problem {
+    // What's wrong and what to do about it
+    pub fn message(&self) -> String {
+        return match self {
+            Problem::MissingTool("nmcli") => String::from("nmcli wasn't found. Install NetworkManager, or make sure nmcli is on PATH."),
+            Problem::MissingTool("iwconfig") => String::from("iwconfig wasn't found, so points can't be measured. Install wireless-tools."),
+            Problem::MissingTool(tool) => format!("{} wasn't found. Install it, or make sure it's on PATH.", tool),
+            Problem::NoInterface => String::from("No wireless interface found. Plug in an adapter or load its driver, then check again."),
+            Problem::ScanFailed(e) => format!("Scanning failed: {}. Check that NetworkManager is running and manages the interface.", e),
+            Problem::SingularGeometry => String::from("The tested points lie on a line, so the location can't be solved. Test a point off that line."),
+        };
+    }
+
+    // The same problem, maybe with different details
+    fn same_kind(&self, other: &Problem) -> bool {
+        return match (self, other) {
+            (Problem::MissingTool(tool), Problem::MissingTool(other_tool)) => tool == other_tool,
+            _ => mem::discriminant(self) == mem::discriminant(other),
+        };
+    }
+}
+
+// Toasts and banners for the GUI. Everything raised here is logged as well.
+#[derive(Default)]
+pub struct Notifications {
+    toasts: Vec<Toast>,
+    problems: Vec<Problem>,
+}
+
+impl Notifications {
+    pub fn success(&mut self, message: String) {
+        info!("{}", message);
+        self.toast(Severity::Success, message);
+    }
+
+    pub fn info(&mut self, message: String) {
+        info!("{}", message);
+        self.toast(Severity::Info, message);
+    }
+
+    pub fn error(&mut self, message: String) {
+        warn!("{}", message);
+        self.toast(Severity::Error, message);
+    }
+
+    // Drops the expired toasts and returns the rest, oldest first
+    pub fn toasts(&mut self) -> &[Toast] {
+        let now = Instant::now();
+        self.toasts.retain(|toast| toast.expires() > now);
+
+        return &self.toasts;
+    }
+
+    pub fn dismiss(&mut self, index: usize) {
+        if index < self.toasts.len() {
+            self.toasts.remove(index);
+        }
+    }
+
+    pub fn problems(&self) -> &[Problem] {
+        return &self.problems;
+    }
+
+    // Replaces any problem of the same kind, so a changed scan error doesn't pile up
+    pub fn raise(&mut self, problem: Problem) {
+        if self.problems.contains(&problem) {
+            return;
+        }
+
+        error!("{}", problem.message());
+        self.problems.retain(|existing| !existing.same_kind(&problem));
+        self.problems.push(problem);
+    }
+
+    // Clears the problem, whatever its details were
+    pub fn resolve(&mut self, problem: &Problem) {
+        self.problems.retain(|existing| !existing.same_kind(problem));
+    }
+
+    fn toast(&mut self, severity: Severity, message: String) {
+        self.toasts.push(Toast { severity, message, shown: Instant::now() });
+    }
+}
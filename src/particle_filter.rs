@@ -0,0 +1,269 @@
+use crate::models::{Point, Wall};
+use crate::trilateration_calc::TrilaterationCalculator;
+
+const STUDENT_T_DOF: f32 = 3.0; // Low degrees of freedom keep the odd wild reading from dominating
+
+#[derive(Clone, Copy)]
+pub struct Particle {
+    pub x: f32,
+    pub y: f32,
+    pub weight: f32,
+}
+
+// Sequential Monte Carlo estimate of the network's position. Handles heavy-tailed indoor RSSI and
+// ambiguous geometry, where the least squares solve picks one of several equally good answers.
+pub struct ParticleFilter {
+    particles: Vec<Particle>,
+    rng: SplitMix64,
+
+    pub count: usize, // Particles to spread when initialising
+    pub rssi_noise_db: f32, // Scale of a single RSSI reading's error
+    pub jitter: f32, // Random walk added to particles after resampling, in plot units
+}
+
+impl Default for ParticleFilter {
+    fn default() -> Self {
+        Self {
+            particles: Vec::new(),
+            rng: SplitMix64(0x5EED_6A70),
+
+            count: 2000,
+            rssi_noise_db: 4.0,
+            jitter: 1.0,
+        }
+    }
+}
+
+impl ParticleFilter {
+    pub fn reset(&mut self) {
+        self.particles.clear();
+    }
+
+    pub fn get_particles(&self) -> &[Particle] {
+        return &self.particles;
+    }
+
+    // Spreads the particles evenly over the area between min and max
+    pub fn initialise(&mut self, min: [f32; 2], max: [f32; 2]) {
+        let weight = 1.0 / self.count.max(1) as f32;
+
+        self.particles = (0..self.count).map(|_| Particle {
+            x: min[0] + (max[0] - min[0]) * self.rng.next_f32(),
+            y: min[1] + (max[1] - min[1]) * self.rng.next_f32(),
+            weight,
+        }).collect();
+    }
+
+    // Area around the usable points, wide enough to hold every range
+    pub fn initialise_around(&mut self, points: &[Point], calc: &TrilaterationCalculator) {
+        let points: Vec<&Point> = points.iter().filter(|point| calc.is_usable(point)).collect();
+        if points.is_empty() {
+            return;
+        }
+
+        let max_range = points.iter().filter_map(|point| calc.estimate_range(point)).fold(1.0, f32::max);
+
+        let min_x = points.iter().map(|point| point.x).fold(f32::INFINITY, f32::min);
+        let min_y = points.iter().map(|point| point.y).fold(f32::INFINITY, f32::min);
+        let max_x = points.iter().map(|point| point.x).fold(f32::NEG_INFINITY, f32::max);
+        let max_y = points.iter().map(|point| point.y).fold(f32::NEG_INFINITY, f32::max);
+
+        self.initialise([min_x - max_range, min_y - max_range], [max_x + max_range, max_y + max_range]);
+    }
+
+    // Weighs every particle by how well it explains the new readings, resampling when the weight
+    // has piled up on too few of them
+    pub fn update(&mut self, points: &[Point], walls: &[Wall], calc: &TrilaterationCalculator) {
+        let tested_points: Vec<&Point> = points.iter().filter(|point| calc.is_usable(point)).collect();
+
+        if tested_points.is_empty() {
+            return;
+        }
+
+        if self.particles.is_empty() {
+            self.initialise_around(points, calc);
+        }
+
+        for particle in self.particles.iter_mut() {
+            for point in tested_points.iter() {
+                particle.weight *= likelihood(particle, point, walls, calc, self.rssi_noise_db);
+            }
+        }
+
+        let total: f32 = self.particles.iter().map(|particle| particle.weight).sum();
+
+        if total <= 0.0 || !total.is_finite() {
+            // Every particle is wildly off, start over around the readings
+            self.initialise_around(points, calc);
+            return;
+        }
+
+        for particle in self.particles.iter_mut() {
+            particle.weight /= total;
+        }
+
+        let effective_count = 1.0 / self.particles.iter().map(|particle| particle.weight.powi(2)).sum::<f32>();
+
+        if effective_count < self.particles.len() as f32 / 2.0 {
+            self.resample();
+        }
+    }
+
+    // Weighted mean of the particles
+    pub fn estimate(&self) -> Option<(f32, f32)> {
+        if self.particles.is_empty() {
+            return None;
+        }
+
+        let x = self.particles.iter().map(|particle| particle.x * particle.weight).sum();
+        let y = self.particles.iter().map(|particle| particle.y * particle.weight).sum();
+
+        return Some((x, y));
+    }
+
+    // Systematic resampling, then a small random walk so duplicates spread out again
+    fn resample(&mut self) {
+        let count = self.particles.len();
+        let step = 1.0 / count as f32;
+        let mut target = self.rng.next_f32() * step;
+        let mut cumulative = 0.0;
+        let mut resampled = Vec::with_capacity(count);
+
+        for particle in self.particles.iter() {
+            cumulative += particle.weight;
+
+            while target < cumulative && resampled.len() < count {
+                resampled.push(Particle { weight: step, ..*particle });
+                target += step;
+            }
+        }
+
+        while resampled.len() < count {
+            resampled.push(Particle { weight: step, ..self.particles[count - 1] });
+        }
+
+        for particle in resampled.iter_mut() {
+            particle.x += self.jitter * self.rng.next_gaussian();
+            particle.y += self.jitter * self.rng.next_gaussian();
+        }
+
+        self.particles = resampled;
+    }
+}
+
+// Student-t likelihood of a point's RSSI if the network sat on the particle
+fn likelihood(particle: &Particle, point: &Point, walls: &[Wall], calc: &TrilaterationCalculator, rssi_noise_db: f32) -> f32 {
+    let net_info = point.net_info.as_ref().unwrap();
+
+    let distance = ((particle.x - point.x).powi(2) + (particle.y - point.y).powi(2) + point.z.powi(2)).sqrt();
+    let wall_loss: f32 = walls.iter()
+        .filter(|wall| wall.crosses([particle.x, particle.y], [point.x, point.y]))
+        .map(|wall| wall.attenuation_db)
+        .sum();
+
+    let expected = calc.expected_rssi(net_info.tx_power.unwrap(), distance) - wall_loss;
+    let error = (net_info.measured_power.unwrap() - expected) / rssi_noise_db;
+
+    return (1.0 + error.powi(2) / STUDENT_T_DOF).powf(-(STUDENT_T_DOF + 1.0) / 2.0);
+}
+
+// Small, seedable generator so the filter doesn't need a dependency
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+
+        return z ^ (z >> 31);
+    }
+
+    // Uniform in [0, 1)
+    fn next_f32(&mut self) -> f32 {
+        return (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32;
+    }
+
+    // Standard normal, Box-Muller
+    fn next_gaussian(&mut self) -> f32 {
+        let u1 = self.next_f32().max(f32::MIN_POSITIVE);
+        let u2 = self.next_f32();
+
+        return (-2.0 * u1.ln()).sqrt() * (std::f32::consts::TAU * u2).cos();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::NetInfo;
+
+    // A point measuring the network at (5, 4) as the calculator would expect it
+    fn reading(x: f32, y: f32, calc: &TrilaterationCalculator) -> Point {
+        let distance = ((x - 5.0).powi(2) + (y - 4.0).powi(2)).sqrt();
+        let net_info = NetInfo { tx_power: Some(-30.0), measured_power: Some(calc.expected_rssi(-30.0, distance)), samples: Vec::new() };
+
+        return Point::new(x, y, Some(net_info));
+    }
+
+    fn estimate(points: &[Point], calc: &TrilaterationCalculator) -> (f32, f32) {
+        return estimate_with_walls(points, &[], calc);
+    }
+
+    fn estimate_with_walls(points: &[Point], walls: &[Wall], calc: &TrilaterationCalculator) -> (f32, f32) {
+        let mut filter = ParticleFilter::default();
+        for _ in 0..5 {
+            filter.update(points, walls, calc);
+        }
+
+        return filter.estimate().unwrap();
+    }
+
+    #[test]
+    fn finds_the_network() {
+        let calc = TrilaterationCalculator::default();
+        let points = [reading(0.0, 0.0, &calc), reading(10.0, 0.0, &calc), reading(0.0, 10.0, &calc), reading(10.0, 10.0, &calc)];
+
+        let (x, y) = estimate(&points, &calc);
+        assert!((x - 5.0).abs() < 1.0 && (y - 4.0).abs() < 1.0, "estimate ({}, {})", x, y);
+    }
+
+    #[test]
+    fn accounts_for_walls() {
+        let calc = TrilaterationCalculator::default();
+        let wall = Wall { start: [6.0, 0.0], end: [10.0, 4.0], attenuation_db: 10.0 };
+
+        // Only the reading from (10, 0) passes through the wall, and comes in 10 dB down
+        let mut points = [reading(0.0, 0.0, &calc), reading(10.0, 0.0, &calc), reading(0.0, 10.0, &calc), reading(10.0, 10.0, &calc)];
+        assert!(wall.crosses([5.0, 4.0], [10.0, 0.0]) && !wall.crosses([5.0, 4.0], [10.0, 10.0]));
+        let net_info = points[1].net_info.as_mut().unwrap();
+        net_info.measured_power = net_info.measured_power.map(|rssi| rssi - 10.0);
+
+        let error = |(x, y): (f32, f32)| ((x - 5.0).powi(2) + (y - 4.0).powi(2)).sqrt();
+        let knowing = estimate_with_walls(&points, &[wall], &calc);
+        let ignoring = estimate(&points, &calc);
+
+        // Without the wall the weak reading pushes the estimate away from (10, 0)
+        assert!(error(knowing) < 1.0, "estimate {:?}", knowing);
+        assert!(error(ignoring) > error(knowing) + 0.5, "{:?} against {:?}", ignoring, knowing);
+    }
+
+    #[test]
+    fn excluded_points_leave_the_estimate_alone() {
+        let calc = TrilaterationCalculator::default();
+        let mut points = vec![reading(0.0, 0.0, &calc), reading(10.0, 0.0, &calc), reading(0.0, 10.0, &calc), reading(10.0, 10.0, &calc)];
+        let expected = estimate(&points, &calc);
+
+        // Reads as if it were right next to the network, and is far enough out to widen the initial spread
+        let mut outlier = reading(5.0, 4.0, &calc);
+        outlier.x = 40.0;
+        outlier.y = 40.0;
+        outlier.excluded = true;
+        points.push(outlier);
+        assert_eq!(estimate(&points, &calc), expected);
+
+        points[4].excluded = false;
+        assert_ne!(estimate(&points, &calc), expected);
+    }
+}
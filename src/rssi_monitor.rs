@@ -0,0 +1,188 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Arc;
+use std::{thread, time};
+
+use crate::device_profile::DeviceProfile;
+use crate::models::LinkReading;
+use crate::network_manager;
+use crate::models::NetInfo;
+
+// Streams link readings from a background thread into a rolling window
+pub struct RssiMonitor {
+    receiver: Option<Receiver<(time::Instant, LinkReading)>>,
+    running: Arc<AtomicBool>,
+    rate_ms: Arc<AtomicU64>, // Shared with the sampling thread so the rate can change while running
+
+    started: time::Instant,
+    window: VecDeque<(f64, LinkReading)>, // Seconds since started, reading
+    pub window_secs: f64, // How far back the rolling window reaches
+    pub device_profile: Option<DeviceProfile>, // Correction applied to readings as they come in
+    pub interface: Option<String>, // Interface to sample, taken up when started
+}
+
+impl Default for RssiMonitor {
+    fn default() -> Self {
+        Self {
+            receiver: None,
+            running: Arc::new(AtomicBool::new(false)),
+            rate_ms: Arc::new(AtomicU64::new(250)),
+
+            started: time::Instant::now(),
+            window: VecDeque::new(),
+            window_secs: 10.0,
+            device_profile: None,
+            interface: None,
+        }
+    }
+}
+
+impl RssiMonitor {
+    pub fn is_running(&self) -> bool {
+        return self.running.load(Ordering::Relaxed);
+    }
+
+    pub fn get_rate_ms(&self) -> u64 {
+        return self.rate_ms.load(Ordering::Relaxed);
+    }
+
+    pub fn set_rate_ms(&self, rate_ms: u64) {
+        self.rate_ms.store(rate_ms.max(1), Ordering::Relaxed);
+    }
+
+    pub fn start(&mut self) {
+        if self.is_running() {
+            return;
+        }
+
+        let (sender, receiver) = mpsc::channel();
+
+        // A fresh flag, so a thread left over from the last run can't be revived
+        self.running = Arc::new(AtomicBool::new(true));
+        let running = Arc::clone(&self.running);
+        let rate_ms = Arc::clone(&self.rate_ms);
+        let interface = self.interface.clone();
+
+        self.receiver = Some(receiver);
+        self.started = time::Instant::now();
+        self.window.clear();
+
+        thread::spawn(move || {
+            while running.load(Ordering::Relaxed) {
+                if let Some(reading) = network_manager::read_link(interface.as_deref()) {
+                    if sender.send((time::Instant::now(), reading)).is_err() {
+                        break;
+                    }
+                }
+
+                thread::sleep(time::Duration::from_millis(rate_ms.load(Ordering::Relaxed)));
+            }
+        });
+    }
+
+    pub fn stop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+        self.receiver = None;
+    }
+
+    // Pulls in everything the sampling thread has sent and drops readings older than the window
+    pub fn poll(&mut self) {
+        if let Some(receiver) = &self.receiver {
+            for (instant, reading) in receiver.try_iter() {
+                let secs = instant.duration_since(self.started).as_secs_f64();
+                let reading = match &self.device_profile {
+                    Some(device_profile) => device_profile.correct(reading),
+                    None => reading,
+                };
+                self.window.push_back((secs, reading));
+            }
+        }
+
+        let now = self.started.elapsed().as_secs_f64();
+        while self.window.front().is_some_and(|(secs, _)| now - secs > self.window_secs) {
+            self.window.pop_front();
+        }
+    }
+
+    pub fn get_window(&self) -> &VecDeque<(f64, LinkReading)> {
+        return &self.window;
+    }
+
+    pub fn mean(&self) -> Option<f32> {
+        return self.capture().and_then(|net_info| net_info.measured_power);
+    }
+
+    pub fn variance(&self) -> Option<f32> {
+        return self.capture().and_then(|net_info| net_info.rssi_std_dev()).map(|std_dev| std_dev.powi(2));
+    }
+
+    // Turns the current window into a measurement
+    pub fn capture(&self) -> Option<NetInfo> {
+        if self.window.is_empty() {
+            return None;
+        }
+
+        let readings: Vec<LinkReading> = self.window.iter().map(|(_, reading)| *reading).collect();
+
+        return Some(NetInfo::from_readings(&readings));
+    }
+}
+
+impl Drop for RssiMonitor {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn monitor(signal_levels: &[f32]) -> RssiMonitor {
+        let mut monitor = RssiMonitor::default();
+        for (i, signal_level) in signal_levels.iter().enumerate() {
+            monitor.window.push_back((i as f64, LinkReading { signal_level: *signal_level, tx_power: 20.0 }));
+        }
+
+        return monitor;
+    }
+
+    #[test]
+    fn empty_window_has_no_statistics() {
+        let monitor = monitor(&[]);
+
+        assert!(monitor.mean().is_none());
+        assert!(monitor.variance().is_none());
+        assert!(monitor.capture().is_none());
+    }
+
+    #[test]
+    fn mean_and_variance_of_the_window() {
+        let cases = [
+            (vec![-60.0], -60.0, 0.0),
+            (vec![-60.0, -60.0, -60.0], -60.0, 0.0),
+            (vec![-50.0, -70.0], -60.0, 100.0),
+            (vec![-62.0, -64.0, -66.0, -68.0], -65.0, 5.0), // Population variance, not the sample one
+        ];
+
+        for (signal_levels, mean, variance) in cases {
+            let monitor = monitor(&signal_levels);
+
+            assert!((monitor.mean().unwrap() - mean).abs() < 1e-4, "{:?}", signal_levels);
+            assert!((monitor.variance().unwrap() - variance).abs() < 1e-3, "{:?}", signal_levels);
+        }
+    }
+
+    #[test]
+    fn polling_drops_readings_older_than_the_window() {
+        let mut monitor = monitor(&[-80.0, -60.0]);
+        monitor.window[0].0 = -20.0; // Well before the window
+        monitor.window[1].0 = 0.0;
+
+        monitor.poll();
+
+        assert_eq!(monitor.get_window().len(), 1);
+        assert_eq!(monitor.mean(), Some(-60.0));
+    }
+}
@@ -0,0 +1,145 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+
+use zeroize::Zeroizing;
+
+const SERVICE: &str = "triangle-gator";
+
+#[derive(Debug)]
+pub enum SecretError {
+    Io(io::Error), // secret-tool missing or unreadable
+    Backend(String), // The secret service refused, e.g. a locked keyring
+}
+
+impl fmt::Display for SecretError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            SecretError::Io(e) => write!(f, "{}", e),
+            SecretError::Backend(e) => write!(f, "secret service: {}", e),
+        };
+    }
+}
+
+impl std::error::Error for SecretError {}
+
+impl From<io::Error> for SecretError {
+    fn from(e: io::Error) -> Self {
+        return SecretError::Io(e);
+    }
+}
+
+// Somewhere to keep network passwords between runs, keyed by SSID
+pub trait SecretStore {
+    fn store(&self, ssid: &str, password: &str) -> Result<(), SecretError>;
+    fn lookup(&self, ssid: &str) -> Result<Option<Zeroizing<String>>, SecretError>;
+    fn clear(&self, ssid: &str) -> Result<(), SecretError>;
+}
+
+// The freedesktop Secret Service (GNOME Keyring, KWallet), through libsecret's secret-tool.
+// Secrets go over stdin and stdout, never on a command line.
+#[derive(Default)]
+pub struct SecretServiceStore;
+
+impl SecretStore for SecretServiceStore {
+    fn store(&self, ssid: &str, password: &str) -> Result<(), SecretError> {
+        let mut child = Command::new("secret-tool")
+            .args(["store", &format!("--label=Wi-Fi password for {}", ssid), "service", SERVICE, "ssid", ssid])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        child.stdin.take().unwrap().write_all(password.as_bytes())?;
+
+        return check(child.wait_with_output()?);
+    }
+
+    fn lookup(&self, ssid: &str) -> Result<Option<Zeroizing<String>>, SecretError> {
+        let mut child = Command::new("secret-tool")
+            .args(["lookup", "service", SERVICE, "ssid", ssid])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+
+        let mut password = Zeroizing::new(String::new());
+        child.stdout.take().unwrap().read_to_string(&mut password)?;
+
+        // Nothing stored exits with a failure and no output
+        if !child.wait()?.success() || password.is_empty() {
+            return Ok(None);
+        }
+
+        return Ok(Some(password));
+    }
+
+    fn clear(&self, ssid: &str) -> Result<(), SecretError> {
+        let output = Command::new("secret-tool")
+            .args(["clear", "service", SERVICE, "ssid", ssid])
+            .output()?;
+
+        return check(output);
+    }
+}
+
+// Keeps secrets for the life of the process only, for tests and machines without a keyring
+#[derive(Default)]
+pub struct MemorySecretStore {
+    secrets: Mutex<HashMap<String, Zeroizing<String>>>,
+}
+
+impl SecretStore for MemorySecretStore {
+    fn store(&self, ssid: &str, password: &str) -> Result<(), SecretError> {
+        self.secrets.lock().unwrap().insert(String::from(ssid), Zeroizing::new(String::from(password)));
+
+        return Ok(());
+    }
+
+    fn lookup(&self, ssid: &str) -> Result<Option<Zeroizing<String>>, SecretError> {
+        return Ok(self.secrets.lock().unwrap().get(ssid).cloned());
+    }
+
+    fn clear(&self, ssid: &str) -> Result<(), SecretError> {
+        self.secrets.lock().unwrap().remove(ssid);
+
+        return Ok(());
+    }
+}
+
+fn check(output: std::process::Output) -> Result<(), SecretError> {
+    if output.status.success() {
+        return Ok(());
+    }
+
+    return Err(SecretError::Backend(String::from_utf8_lossy(&output.stderr).trim().to_string()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stores_looks_up_and_clears() {
+        let store = MemorySecretStore::default();
+        assert!(store.lookup("Home").unwrap().is_none());
+
+        store.store("Home", "hunter22").unwrap();
+        store.store("Office", "correct horse").unwrap();
+        assert_eq!(store.lookup("Home").unwrap().as_deref().map(String::as_str), Some("hunter22"));
+
+        store.store("Home", "hunter23").unwrap();
+        assert_eq!(store.lookup("Home").unwrap().as_deref().map(String::as_str), Some("hunter23"));
+
+        // The copy handed back is the caller's own, and outlives clearing the store
+        let password = store.lookup("Home").unwrap().unwrap();
+        store.clear("Home").unwrap();
+        assert!(store.lookup("Home").unwrap().is_none());
+        assert_eq!(password.as_str(), "hunter23");
+        assert!(store.lookup("Office").unwrap().is_some());
+
+        // Clearing something that isn't there is fine, like secret-tool
+        store.clear("Home").unwrap();
+    }
+}
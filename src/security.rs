@@ -0,0 +1,202 @@
+use std::fmt;
+use std::path::PathBuf;
+
+use serde::Serialize;
+use zeroize::Zeroizing;
+
+// How a network authenticates, parsed from nmcli's SECURITY column
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize)]
+pub enum SecurityKind {
+    #[default]
+    Open,
+    Wep,
+    WpaPsk, // WPA/WPA2 personal, including WPA2/WPA3 transition networks
+    Sae, // WPA3 personal only
+    Enterprise, // 802.1X
+    Owe, // Enhanced Open, encrypted but no credentials
+}
+
+impl SecurityKind {
+    pub const ALL: [SecurityKind; 6] = [
+        SecurityKind::Open,
+        SecurityKind::Wep,
+        SecurityKind::WpaPsk,
+        SecurityKind::Sae,
+        SecurityKind::Enterprise,
+        SecurityKind::Owe,
+    ];
+
+    // e.g. "", "--", "WEP", "WPA1 WPA2", "WPA2 802.1X", "WPA3", "OWE"
+    pub fn parse(security: &str) -> SecurityKind {
+        let flags: Vec<&str> = security.split_whitespace().collect();
+
+        if flags.contains(&"802.1X") {
+            return SecurityKind::Enterprise;
+        }
+        if flags.iter().any(|flag| flag.starts_with("WPA1") || flag.starts_with("WPA2")) {
+            return SecurityKind::WpaPsk;
+        }
+        if flags.contains(&"WPA3") {
+            return SecurityKind::Sae;
+        }
+        if flags.contains(&"WEP") {
+            return SecurityKind::Wep;
+        }
+        if flags.contains(&"OWE") {
+            return SecurityKind::Owe;
+        }
+
+        return SecurityKind::Open;
+    }
+
+    pub fn needs_password(&self) -> bool {
+        return matches!(self, SecurityKind::Wep | SecurityKind::WpaPsk | SecurityKind::Sae | SecurityKind::Enterprise);
+    }
+
+    // nmcli's wifi-sec.key-mgmt for this kind, None if the network has no security setting at all
+    pub fn key_mgmt(&self) -> Option<&'static str> {
+        return match self {
+            SecurityKind::Open => None,
+            SecurityKind::Wep => Some("none"),
+            SecurityKind::WpaPsk => Some("wpa-psk"),
+            SecurityKind::Sae => Some("sae"),
+            SecurityKind::Enterprise => Some("wpa-eap"),
+            SecurityKind::Owe => Some("owe"),
+        };
+    }
+
+    // Setting the password goes into, when handed to nmcli in a passwd-file
+    pub fn password_setting(&self) -> Option<&'static str> {
+        return match self {
+            SecurityKind::Wep => Some("802-11-wireless-security.wep-key0"),
+            SecurityKind::WpaPsk | SecurityKind::Sae => Some("802-11-wireless-security.psk"),
+            SecurityKind::Enterprise => Some("802-1x.password"),
+            SecurityKind::Open | SecurityKind::Owe => None,
+        };
+    }
+}
+
+impl fmt::Display for SecurityKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            SecurityKind::Open => write!(f, "Open"),
+            SecurityKind::Wep => write!(f, "WEP"),
+            SecurityKind::WpaPsk => write!(f, "WPA-PSK"),
+            SecurityKind::Sae => write!(f, "WPA3-SAE"),
+            SecurityKind::Enterprise => write!(f, "802.1X"),
+            SecurityKind::Owe => write!(f, "OWE"),
+        };
+    }
+}
+
+#[derive(Clone, Copy, Default, PartialEq)]
+pub enum EapMethod {
+    #[default]
+    Peap,
+    Ttls,
+}
+
+impl EapMethod {
+    pub const ALL: [EapMethod; 2] = [EapMethod::Peap, EapMethod::Ttls];
+
+    pub fn nmcli_name(&self) -> &'static str {
+        return match self {
+            EapMethod::Peap => "peap",
+            EapMethod::Ttls => "ttls",
+        };
+    }
+}
+
+impl fmt::Display for EapMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            EapMethod::Peap => write!(f, "PEAP"),
+            EapMethod::Ttls => write!(f, "TTLS"),
+        };
+    }
+}
+
+// Inner authentication, inside the PEAP/TTLS tunnel
+#[derive(Clone, Copy, Default, PartialEq)]
+pub enum Phase2Auth {
+    #[default]
+    MsChapV2,
+    Pap,
+    Gtc,
+}
+
+impl Phase2Auth {
+    pub const ALL: [Phase2Auth; 3] = [Phase2Auth::MsChapV2, Phase2Auth::Pap, Phase2Auth::Gtc];
+
+    pub fn nmcli_name(&self) -> &'static str {
+        return match self {
+            Phase2Auth::MsChapV2 => "mschapv2",
+            Phase2Auth::Pap => "pap",
+            Phase2Auth::Gtc => "gtc",
+        };
+    }
+}
+
+impl fmt::Display for Phase2Auth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            Phase2Auth::MsChapV2 => write!(f, "MSCHAPv2"),
+            Phase2Auth::Pap => write!(f, "PAP"),
+            Phase2Auth::Gtc => write!(f, "GTC"),
+        };
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct EnterpriseCredentials {
+    pub eap: EapMethod,
+    pub phase2: Phase2Auth,
+    pub identity: String,
+    pub anonymous_identity: String, // Outer identity, empty to send the real one
+    pub ca_cert: Option<PathBuf>, // None skips server certificate validation
+    pub password: Zeroizing<String>,
+}
+
+// Whatever the network's security kind needs to join it
+#[derive(Clone)]
+pub enum Credentials {
+    None,
+    Password(Zeroizing<String>),
+    Enterprise(EnterpriseCredentials),
+}
+
+impl Credentials {
+    pub fn password(&self) -> &str {
+        return match self {
+            Credentials::None => "",
+            Credentials::Password(password) => password,
+            Credentials::Enterprise(enterprise) => &enterprise.password,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_nmcli_security() {
+        let cases = [
+            ("", SecurityKind::Open),
+            ("--", SecurityKind::Open),
+            ("WEP", SecurityKind::Wep),
+            ("WPA1", SecurityKind::WpaPsk),
+            ("WPA1 WPA2", SecurityKind::WpaPsk),
+            ("WPA2 WPA3", SecurityKind::WpaPsk), // Transition mode, PSK still works
+            ("WPA3", SecurityKind::Sae),
+            ("WPA3 SAE", SecurityKind::Sae),
+            ("WPA2 802.1X", SecurityKind::Enterprise),
+            ("WPA1 WPA2 802.1X", SecurityKind::Enterprise),
+            ("OWE", SecurityKind::Owe),
+        ];
+
+        for (security, expected) in cases {
+            assert_eq!(SecurityKind::parse(security), expected, "{:?}", security);
+        }
+    }
+}
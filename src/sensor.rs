@@ -0,0 +1,341 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use std::thread;
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::{LinkReading, NetInfo, Point};
+
+// Bumped whenever SensorReport changes shape, reports from other versions are rejected
+pub const PROTOCOL_VERSION: u32 = 1;
+
+pub const DEFAULT_PORT: u16 = 47_800;
+
+pub const DEFAULT_MAX_AGE: Duration = Duration::from_secs(3); // Readings older than this aren't considered simultaneous
+
+const MAX_REPORT_SIZE: usize = 1024;
+
+// One RSSI reading from an agent at a fixed position, sent as a single JSON datagram
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SensorReport {
+    pub version: u32,
+    pub sensor_id: String,
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub signal_level: f32, // dBm
+    pub tx_power: f32, // dBm
+    pub timestamp_ms: u64, // Agent clock, for logging only
+}
+
+#[derive(Debug)]
+pub enum SensorError {
+    Malformed(serde_json::Error),
+    UnsupportedVersion(u32),
+}
+
+impl fmt::Display for SensorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            SensorError::Malformed(e) => write!(f, "malformed sensor report: {}", e),
+            SensorError::UnsupportedVersion(version) => write!(f, "unsupported protocol version {} (expected {})", version, PROTOCOL_VERSION),
+        };
+    }
+}
+
+impl std::error::Error for SensorError {}
+
+impl SensorReport {
+    pub fn new(sensor_id: &str, position: &Point, reading: LinkReading) -> SensorReport {
+        let timestamp_ms = SystemTime::now().duration_since(UNIX_EPOCH).map(|since| since.as_millis() as u64).unwrap_or(0);
+
+        return SensorReport {
+            version: PROTOCOL_VERSION,
+            sensor_id: sensor_id.to_string(),
+            x: position.x,
+            y: position.y,
+            z: position.z,
+            signal_level: reading.signal_level,
+            tx_power: reading.tx_power,
+            timestamp_ms,
+        };
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        return serde_json::to_vec(self).unwrap();
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<SensorReport, SensorError> {
+        // Check the version before the rest of the shape, so old agents get a useful error
+        let value: serde_json::Value = serde_json::from_slice(bytes).map_err(SensorError::Malformed)?;
+        let version = value.get("version").and_then(|version| version.as_u64()).unwrap_or(0) as u32;
+
+        if version != PROTOCOL_VERSION {
+            return Err(SensorError::UnsupportedVersion(version));
+        }
+
+        return serde_json::from_value(value).map_err(SensorError::Malformed);
+    }
+
+    pub fn reading(&self) -> LinkReading {
+        return LinkReading { signal_level: self.signal_level, tx_power: self.tx_power };
+    }
+}
+
+// Samples RSSI at a fixed position and streams it to a collector
+pub struct SensorAgent {
+    pub sensor_id: String,
+    pub position: Point,
+    pub collector: SocketAddr,
+    pub rate_ms: u64,
+}
+
+impl SensorAgent {
+    pub fn new(sensor_id: &str, position: Point, collector: impl ToSocketAddrs) -> io::Result<SensorAgent> {
+        let collector = collector.to_socket_addrs()?.next().ok_or(io::Error::new(io::ErrorKind::InvalidInput, "collector address did not resolve"))?;
+
+        return Ok(SensorAgent { sensor_id: sensor_id.to_string(), position, collector, rate_ms: 500 });
+    }
+
+    // Sends a report for every reading until running is cleared
+    pub fn run(&self, running: &AtomicBool, mut read_link: impl FnMut() -> Option<LinkReading>) -> io::Result<()> {
+        let socket = UdpSocket::bind(if self.collector.is_ipv4() { "0.0.0.0:0" } else { "[::]:0" })?;
+
+        while running.load(Ordering::Relaxed) {
+            if let Some(reading) = read_link() {
+                let report = SensorReport::new(&self.sensor_id, &self.position, reading);
+                socket.send_to(&report.encode(), self.collector)?;
+            }
+
+            thread::sleep(Duration::from_millis(self.rate_ms));
+        }
+
+        return Ok(());
+    }
+}
+
+// Latest readings of one agent
+pub struct SensorState {
+    pub report: SensorReport, // Most recent report
+    pub last_seen: Instant,
+    recent: VecDeque<(Instant, LinkReading)>,
+}
+
+// Listens for agents and keeps their recent readings, so simultaneous readings can be solved together
+pub struct Collector {
+    local_addr: SocketAddr,
+    running: Arc<AtomicBool>,
+    sensors: Arc<Mutex<HashMap<String, SensorState>>>,
+    last_error: Arc<Mutex<Option<String>>>,
+    max_age: Duration, // Readings older than this aren't considered simultaneous
+}
+
+impl Collector {
+    pub fn bind(addr: impl ToSocketAddrs, max_age: Duration) -> io::Result<Collector> {
+        let socket = UdpSocket::bind(addr)?;
+        socket.set_read_timeout(Some(Duration::from_millis(200)))?;
+
+        let collector = Collector {
+            local_addr: socket.local_addr()?,
+            running: Arc::new(AtomicBool::new(true)),
+            sensors: Arc::new(Mutex::new(HashMap::new())),
+            last_error: Arc::new(Mutex::new(None)),
+            max_age,
+        };
+
+        let running = Arc::clone(&collector.running);
+        let sensors = Arc::clone(&collector.sensors);
+        let last_error = Arc::clone(&collector.last_error);
+
+        thread::spawn(move || {
+            let mut buffer = [0u8; MAX_REPORT_SIZE];
+
+            while running.load(Ordering::Relaxed) {
+                let Ok((size, _)) = socket.recv_from(&mut buffer) else {
+                    continue;
+                };
+
+                match SensorReport::decode(&buffer[..size]) {
+                    Ok(report) => {
+                        let now = Instant::now();
+                        let mut sensors = sensors.lock().unwrap();
+                        let state = sensors.entry(report.sensor_id.clone()).or_insert(SensorState {
+                            report: report.clone(),
+                            last_seen: now,
+                            recent: VecDeque::new(),
+                        });
+
+                        state.recent.push_back((now, report.reading()));
+                        while state.recent.front().is_some_and(|(seen, _)| now.duration_since(*seen) > max_age) {
+                            state.recent.pop_front();
+                        }
+                        state.report = report;
+                        state.last_seen = now;
+                    }
+                    Err(e) => *last_error.lock().unwrap() = Some(e.to_string()),
+                }
+            }
+        });
+
+        return Ok(collector);
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        return self.local_addr;
+    }
+
+    pub fn max_age(&self) -> Duration {
+        return self.max_age;
+    }
+
+    pub fn stop(&self) {
+        self.running.store(false, Ordering::Relaxed);
+    }
+
+    // Last report that couldn't be understood since this was last called, if any
+    pub fn take_last_error(&self) -> Option<String> {
+        return self.last_error.lock().unwrap().take();
+    }
+
+    // Every agent heard from, as (id, position, last signal level, seconds since last heard)
+    pub fn get_sensors(&self) -> Vec<(String, Point, f32, f32)> {
+        let sensors = self.sensors.lock().unwrap();
+
+        let mut list: Vec<(String, Point, f32, f32)> = sensors.iter().map(|(id, state)| {
+            let position = report_position(&state.report);
+            (id.clone(), position, state.report.signal_level, state.last_seen.elapsed().as_secs_f32())
+        }).collect();
+        list.sort_by(|a, b| a.0.cmp(&b.0));
+
+        return list;
+    }
+
+    // Points for every agent heard from within max_age, each measured from its readings in that window
+    pub fn snapshot(&self) -> Vec<Point> {
+        let sensors = self.sensors.lock().unwrap();
+        let now = Instant::now();
+
+        let mut ids: Vec<&String> = sensors.keys().collect();
+        ids.sort();
+
+        return ids.into_iter().filter_map(|id| {
+            let state = &sensors[id];
+            let readings: Vec<LinkReading> = state.recent.iter()
+                .filter(|(seen, _)| now.duration_since(*seen) <= self.max_age)
+                .map(|(_, reading)| *reading)
+                .collect();
+
+            if readings.is_empty() {
+                return None;
+            }
+
+            let mut point = report_position(&state.report);
+            point.net_info = Some(NetInfo::from_readings(&readings));
+
+            Some(point)
+        }).collect();
+    }
+}
+
+impl Drop for Collector {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+fn report_position(report: &SensorReport) -> Point {
+    let mut point = Point::new(report.x, report.y, None);
+    point.z = report.z;
+
+    return point;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Polls until the collector's thread has caught up, or gives up after a couple of seconds
+    fn wait_for<T>(mut check: impl FnMut() -> Option<T>) -> Option<T> {
+        let started = Instant::now();
+
+        while started.elapsed() < Duration::from_secs(2) {
+            if let Some(value) = check() {
+                return Some(value);
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        return None;
+    }
+
+    #[test]
+    fn collects_readings_from_an_agent() {
+        let collector = Collector::bind("127.0.0.1:0", DEFAULT_MAX_AGE).unwrap();
+
+        let mut position = Point::new(3.0, 4.0, None);
+        position.z = 1.5;
+        let mut agent = SensorAgent::new("kitchen", position, collector.local_addr()).unwrap();
+        agent.rate_ms = 5;
+
+        // A few ticks, then stop
+        let running = AtomicBool::new(true);
+        let mut ticks = 0;
+        agent.run(&running, || {
+            ticks += 1;
+            if ticks == 4 {
+                running.store(false, Ordering::Relaxed);
+            }
+            Some(LinkReading { signal_level: -60.0, tx_power: -30.0 })
+        }).unwrap();
+
+        let all_heard = |points: &Vec<Point>| points.iter().any(|point| point.net_info.as_ref().is_some_and(|net_info| net_info.samples.len() == 4));
+        let points = wait_for(|| Some(collector.snapshot()).filter(all_heard)).unwrap();
+        assert_eq!(points.len(), 1);
+        assert_eq!((points[0].x, points[0].y, points[0].z), (3.0, 4.0, 1.5));
+
+        let net_info = points[0].net_info.as_ref().unwrap();
+        assert_eq!(net_info.measured_power, Some(-60.0));
+        assert_eq!(net_info.tx_power, Some(-30.0));
+
+        let sensors = collector.get_sensors();
+        assert_eq!(sensors.len(), 1);
+        assert_eq!(sensors[0].0, "kitchen");
+        assert!(collector.take_last_error().is_none());
+    }
+
+    #[test]
+    fn rejects_other_protocol_versions() {
+        let collector = Collector::bind("127.0.0.1:0", DEFAULT_MAX_AGE).unwrap();
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+
+        let mut report = SensorReport::new("hall", &Point::new(0.0, 0.0, None), LinkReading { signal_level: -70.0, tx_power: -30.0 });
+        report.version = PROTOCOL_VERSION + 1;
+        socket.send_to(&report.encode(), collector.local_addr()).unwrap();
+
+        let error = wait_for(|| collector.take_last_error()).unwrap();
+        assert!(error.contains("unsupported protocol version"), "{}", error);
+        assert!(collector.snapshot().is_empty());
+        assert!(collector.take_last_error().is_none());
+    }
+
+    #[test]
+    fn forgets_readings_older_than_max_age() {
+        let collector = Collector::bind("127.0.0.1:0", Duration::from_millis(100)).unwrap();
+        assert_eq!(collector.max_age(), Duration::from_millis(100));
+
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let report = SensorReport::new("hall", &Point::new(0.0, 0.0, None), LinkReading { signal_level: -70.0, tx_power: -30.0 });
+        socket.send_to(&report.encode(), collector.local_addr()).unwrap();
+
+        assert!(wait_for(|| Some(collector.snapshot()).filter(|points| !points.is_empty())).is_some());
+
+        thread::sleep(Duration::from_millis(150));
+        assert!(collector.snapshot().is_empty());
+        assert_eq!(collector.get_sensors().len(), 1); // Still listed, just stale
+    }
+}
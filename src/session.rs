@@ -0,0 +1,256 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::export::{self, ExportError, ExportFormat};
+use crate::floor_plan::FloorPlan;
+use crate::geo::GeoOrigin;
+use crate::models::{Location, Point, Wall};
+use crate::trilateration_calc::TrilaterationCalculator;
+use crate::units::DistanceUnit;
+
+#[derive(Clone, Serialize, Deserialize)] 
+#[serde(default)]
+pub struct Session {
+    pub ssid: Option<String>, // Network being surveyed
+    pub unit: DistanceUnit, // Unit of every coordinate and distance in the session
+    pub points: Vec<Point>,
+    pub path_loss_exponent: f32,
+    pub location: Option<Location>, // Estimated location of the network
+    pub origin: Option<GeoOrigin>, // Where (0, 0) is on the globe, if the survey is georeferenced
+    pub floor_plan: Option<FloorPlan>, // Background image, bottom left corner on (0, 0)
+    pub walls: Vec<Wall>,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self {
+            ssid: None,
+            unit: DistanceUnit::Metres,
+            points: vec![
+                Point::new(0.0, 0.0, None),  // Bottom left
+                Point::new(100.0, 0.0, None),  // Bottom right
+                Point::new(50.0, 86.0, None),  // Top
+            ],
+            path_loss_exponent: 3.0,
+            location: None,
+            origin: None,
+            floor_plan: None,
+            walls: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum SessionError {
+    Io(io::Error),
+    Json(serde_json::Error),
+}
+
+impl fmt::Display for SessionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            SessionError::Io(e) => write!(f, "{}", e),
+            SessionError::Json(e) => write!(f, "invalid session file: {}", e),
+        };
+    }
+}
+
+impl std::error::Error for SessionError {}
+
+impl From<io::Error> for SessionError {
+    fn from(e: io::Error) -> Self {
+        return SessionError::Io(e);
+    }
+}
+
+impl From<serde_json::Error> for SessionError {
+    fn from(e: serde_json::Error) -> Self {
+        return SessionError::Json(e);
+    }
+}
+
+impl Session {
+    pub fn load(path: &Path) -> Result<Session, SessionError> {
+        let contents = fs::read_to_string(path)?;
+
+        return Ok(serde_json::from_str(&contents)?);
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), SessionError> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+
+        return Ok(());
+    }
+
+    // Estimates the location of the network from the tested points
+    pub fn solve(&mut self) -> Option<&Location> {
+        self.location = self.calculator().get_location(&self.points);
+
+        return self.location.as_ref();
+    }
+
+    // Estimates as the path loss exponent sweeps from one value to another, to show how much the answer hangs on it.
+    // Exponents the points can't be solved at are left out.
+    pub fn exponent_sweep(&self, from: f32, to: f32, steps: usize) -> Vec<(f32, Location)> {
+        let mut calc = self.calculator();
+        let mut sweep = Vec::new();
+
+        for step in 0..=steps {
+            let exponent = from + (to - from) * step as f32 / steps.max(1) as f32;
+            calc.set_path_loss_exponent(exponent);
+
+            if let Some(location) = calc.get_location(&self.points) {
+                sweep.push((exponent, location));
+            }
+        }
+
+        return sweep;
+    }
+
+    pub fn is_georeferenced(&self) -> bool {
+        return self.origin.is_some();
+    }
+
+    // Point at a latitude/longitude, in the session's local frame. None if the session isn't georeferenced.
+    pub fn point_at(&self, latitude: f64, longitude: f64) -> Option<Point> {
+        let (x, y) = self.to_local(latitude, longitude)?;
+
+        return Some(Point::new(x, y, None));
+    }
+
+    // Latitude/longitude of the estimated location, if there is one and the session is georeferenced
+    pub fn location_geodetic(&self) -> Option<(f64, f64)> {
+        let location = self.location.as_ref()?;
+
+        return self.to_geodetic(location.x, location.y);
+    }
+
+    // Latitude/longitude of a spot in the session's frame, the ENU frame being in metres
+    pub fn to_geodetic(&self, x: f32, y: f32) -> Option<(f64, f64)> {
+        let origin = self.origin.as_ref()?;
+
+        return Some(origin.to_geodetic(f64::from(self.unit.to_metres(x)), f64::from(self.unit.to_metres(y))));
+    }
+
+    // Spot in the session's frame at a latitude/longitude
+    pub fn to_local(&self, latitude: f64, longitude: f64) -> Option<(f32, f32)> {
+        let (east, north) = self.origin.as_ref()?.to_local(latitude, longitude);
+
+        return Some((self.unit.from_metres(east as f32), self.unit.from_metres(north as f32)));
+    }
+
+    // Calculator set up with the session's path loss exponent and unit
+    pub fn calculator(&self) -> TrilaterationCalculator {
+        let mut calc = TrilaterationCalculator::default();
+        calc.set_path_loss_exponent(self.path_loss_exponent);
+        calc.set_unit(self.unit);
+
+        return calc;
+    }
+
+    // Switches the session to another unit, converting every coordinate so nothing moves
+    pub fn set_unit(&mut self, unit: DistanceUnit) {
+        let factor = self.unit.convert(1.0, unit);
+
+        for point in self.points.iter_mut() {
+            point.z *= factor;
+        }
+
+        self.scale(factor);
+        self.unit = unit;
+    }
+
+    // Scales every coordinate in the session about the origin
+    pub fn scale(&mut self, factor: f32) {
+        for point in self.points.iter_mut() {
+            point.x *= factor;
+            point.y *= factor;
+        }
+
+        for wall in self.walls.iter_mut() {
+            wall.start = wall.start.map(|coord| coord * factor);
+            wall.end = wall.end.map(|coord| coord * factor);
+        }
+
+        if let Some(location) = self.location.as_mut() {
+            location.x *= factor;
+            location.y *= factor;
+            location.uncertainty *= factor;
+        }
+    }
+
+    // Rescales the floor plan, and every coordinate with it, so points stay on the same spot of the building
+    pub fn calibrate(&mut self, factor: f32) {
+        if let Some(floor_plan) = self.floor_plan.as_mut() {
+            floor_plan.metres_per_pixel *= factor;
+        }
+
+        self.scale(factor);
+    }
+
+    pub fn to_csv(&self) -> String {
+        return export::to_csv(self);
+    }
+
+    pub fn to_geojson(&self) -> Result<String, ExportError> {
+        return export::to_geojson(self);
+    }
+
+    pub fn to_kml(&self) -> Result<String, ExportError> {
+        return export::to_kml(self);
+    }
+
+    pub fn export(&self, format: ExportFormat, path: &Path) -> Result<(), ExportError> {
+        let contents = match format {
+            ExportFormat::Csv => self.to_csv(),
+            ExportFormat::GeoJson => self.to_geojson()?,
+            ExportFormat::Kml => self.to_kml()?,
+        };
+
+        fs::write(path, contents)?;
+
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn calibrating_keeps_points_on_the_same_spot_of_the_plan() {
+        let mut session = Session {
+            location: Some(Location { x: 50.0, y: 20.0, uncertainty: 4.0 }),
+            floor_plan: Some(FloorPlan::new(PathBuf::from("plan.png"), 1000, 500, DistanceUnit::Metres)),
+            ..Session::default()
+        };
+
+        session.calibrate(0.5);
+
+        let coordinates: Vec<(f32, f32)> = session.points.iter().map(|point| (point.x, point.y)).collect();
+        assert_eq!(coordinates, [(0.0, 0.0), (50.0, 0.0), (25.0, 43.0)]);
+
+        let location = session.location.as_ref().unwrap();
+        assert_eq!((location.x, location.y, location.uncertainty), (25.0, 10.0, 2.0));
+
+        // The plan shrinks with them, so the bottom right point is still on its right edge
+        let floor_plan = session.floor_plan.as_ref().unwrap();
+        assert_eq!(floor_plan.metres_per_pixel, 0.05);
+        assert_eq!(floor_plan.size(session.unit), [50.0, 25.0]);
+    }
+
+    #[test]
+    fn calibrating_without_a_plan_still_scales() {
+        let mut session = Session::default();
+
+        session.calibrate(2.0);
+
+        assert_eq!((session.points[1].x, session.points[2].y), (200.0, 172.0));
+        assert!(session.floor_plan.is_none());
+    }
+}
@@ -0,0 +1,109 @@
+use serde::{Deserialize, Serialize};
+
+use crate::gpsd;
+use crate::network_list::NetworkColumn;
+use crate::sensor;
+use crate::units::DistanceUnit;
+
+// Preferences kept between launches. Fields missing from an older save take their defaults.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub sample_scale: u16, // Readings averaged per tested point
+    pub sample_length: u64, // ms between readings
+    pub path_loss_exponent: f32, // Starting exponent for new sessions
+    pub unit: DistanceUnit,
+    pub interface: Option<String>, // Wireless interface to use, the backends' default if None
+
+    pub zoom_speed: f32,
+    pub scroll_speed: f32,
+    pub lock_x: bool, // Keep the plot's x axis from zooming and scrolling
+    pub lock_y: bool,
+    pub ctrl_to_zoom: bool, // Zoom with ctrl held and scroll without, instead of the other way round
+    pub shift_to_horizontal: bool, // Scroll sideways with shift held, instead of without
+
+    // Which panels are open, their sizes are kept by egui itself
+    pub show_networks_panel: bool,
+    pub show_survey_panel: bool,
+    pub show_bottom_panel: bool, // Log and live RSSI
+
+    pub network_sort: NetworkColumn, // Column the network list is sorted by
+    pub network_sort_ascending: bool,
+
+    pub remember_password: bool,
+    pub export_path: String, // Without extension
+    pub gpsd_addr: String,
+    pub collector_port: u16,
+    pub wall_attenuation: f32, // dB lost through walls drawn next
+    pub log_path: String,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        return Settings {
+            sample_scale: 10,
+            sample_length: 200,
+            path_loss_exponent: 3.0,
+            unit: DistanceUnit::default(),
+            interface: None,
+
+            zoom_speed: 1.0,
+            scroll_speed: 1.0,
+            lock_x: false,
+            lock_y: false,
+            ctrl_to_zoom: false,
+            shift_to_horizontal: false,
+
+            show_networks_panel: true,
+            show_survey_panel: true,
+            show_bottom_panel: true,
+
+            network_sort: NetworkColumn::default(),
+            network_sort_ascending: false,
+
+            remember_password: false,
+            export_path: String::from("survey"),
+            gpsd_addr: String::from(gpsd::DEFAULT_ADDR),
+            collector_port: sensor::DEFAULT_PORT,
+            wall_attenuation: 5.0,
+            log_path: String::from("triangle-gator.log"),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips() {
+        let settings = Settings {
+            path_loss_exponent: 3.7,
+            unit: DistanceUnit::Feet,
+            interface: Some(String::from("wlan1")),
+            show_survey_panel: false,
+            network_sort: NetworkColumn::Ssid,
+            network_sort_ascending: true,
+            ..Settings::default()
+        };
+
+        let saved = serde_json::to_string(&settings).unwrap();
+        let loaded: Settings = serde_json::from_str(&saved).unwrap();
+
+        assert_eq!(loaded.path_loss_exponent, 3.7);
+        assert_eq!(loaded.unit, DistanceUnit::Feet);
+        assert_eq!(loaded.interface.as_deref(), Some("wlan1"));
+        assert!(!loaded.show_survey_panel);
+        assert_eq!(loaded.network_sort, NetworkColumn::Ssid);
+        assert!(loaded.network_sort_ascending);
+    }
+
+    #[test]
+    fn older_saves_take_defaults() {
+        let loaded: Settings = serde_json::from_str(r#"{ "path_loss_exponent": 2.5 }"#).unwrap();
+
+        assert_eq!(loaded.path_loss_exponent, 2.5);
+        assert_eq!(loaded.sample_scale, Settings::default().sample_scale);
+        assert!(loaded.show_networks_panel);
+    }
+}
@@ -0,0 +1,275 @@
+use nalgebra::{Matrix2, Matrix4, RowVector4, Vector4};
+
+use crate::models::Point;
+use crate::trilateration_calc::TrilaterationCalculator;
+
+// A filtered position of the network at one moment
+#[derive(Clone)]
+pub struct TrackPoint {
+    pub time: f64, // Seconds
+    pub x: f32,
+    pub y: f32,
+    pub covariance: Matrix2<f32>, // Position covariance
+}
+
+impl TrackPoint {
+    // Semi-axes and rotation (radians) of the ellipse holding the position with the given number of standard deviations
+    pub fn ellipse(&self, sigmas: f32) -> (f32, f32, f32) {
+        let eigen = self.covariance.symmetric_eigen();
+        let (major, minor) = if eigen.eigenvalues[0] >= eigen.eigenvalues[1] { (0, 1) } else { (1, 0) };
+
+        let major_axis = eigen.eigenvectors.column(major);
+        let angle = major_axis[1].atan2(major_axis[0]);
+
+        return (
+            sigmas * eigen.eigenvalues[major].max(0.0).sqrt(),
+            sigmas * eigen.eigenvalues[minor].max(0.0).sqrt(),
+            angle,
+        );
+    }
+}
+
+// Extended Kalman filter over the network's position and velocity, fed with ranges from
+// fixed sensors or from a moving observer. State is [x, y, vx, vy].
+pub struct Tracker {
+    state: Vector4<f32>,
+    covariance: Matrix4<f32>,
+    last_time: Option<f64>,
+    track: Vec<TrackPoint>,
+
+    pub process_noise: f32, // How hard the network may accelerate, in units per second squared
+    pub rssi_noise_db: f32, // Standard deviation of a single RSSI reading
+}
+
+impl Default for Tracker {
+    fn default() -> Self {
+        Self {
+            state: Vector4::zeros(),
+            covariance: Matrix4::identity(),
+            last_time: None,
+            track: Vec::new(),
+
+            process_noise: 0.5,
+            rssi_noise_db: 4.0,
+        }
+    }
+}
+
+impl Tracker {
+    pub fn reset(&mut self) {
+        self.last_time = None;
+        self.track.clear();
+    }
+
+    pub fn get_track(&self) -> &[TrackPoint] {
+        return &self.track;
+    }
+
+    pub fn estimate(&self) -> Option<&TrackPoint> {
+        return self.track.last();
+    }
+
+    // Folds the ranges of the tested points that aren't excluded, all taken at `time`, into the track
+    pub fn update(&mut self, time: f64, points: &[Point], calc: &TrilaterationCalculator) -> Option<&TrackPoint> {
+        let ranges: Vec<(f32, f32, f32)> = points.iter()
+            .filter(|point| calc.is_usable(point))
+            .filter_map(|point| calc.estimate_range(point).map(|range| (point.x, point.y, range)))
+            .collect();
+
+        if ranges.is_empty() {
+            return None;
+        }
+
+        match self.last_time {
+            None => self.initialise(points, &ranges, calc),
+            Some(last_time) => self.predict((time - last_time).max(0.0) as f32),
+        }
+        self.last_time = Some(time);
+
+        for (x, y, range) in ranges {
+            self.correct(x, y, range, calc.get_path_loss_exponent());
+        }
+
+        self.track.push(TrackPoint {
+            time,
+            x: self.state[0],
+            y: self.state[1],
+            covariance: self.covariance.fixed_view::<2, 2>(0, 0).into_owned(),
+        });
+
+        return self.track.last();
+    }
+
+    // Starts from a one-shot solve if there are enough points, otherwise from the middle of the observers
+    fn initialise(&mut self, points: &[Point], ranges: &[(f32, f32, f32)], calc: &TrilaterationCalculator) {
+        let max_range = ranges.iter().map(|(_, _, range)| *range).fold(1.0, f32::max);
+
+        if let Some(location) = calc.get_location(points) {
+            self.state = Vector4::new(location.x, location.y, 0.0, 0.0);
+            let variance = location.uncertainty.max(1.0).powi(2);
+            self.covariance = Matrix4::from_diagonal(&Vector4::new(variance, variance, 1.0, 1.0));
+        } else {
+            let count = ranges.len() as f32;
+            let x = ranges.iter().map(|(x, _, _)| x).sum::<f32>() / count;
+            let y = ranges.iter().map(|(_, y, _)| y).sum::<f32>() / count;
+
+            self.state = Vector4::new(x, y, 0.0, 0.0);
+            let variance = max_range.powi(2);
+            self.covariance = Matrix4::from_diagonal(&Vector4::new(variance, variance, 1.0, 1.0));
+        }
+    }
+
+    // Constant velocity motion model
+    fn predict(&mut self, dt: f32) {
+        let transition = Matrix4::new(
+            1.0, 0.0, dt, 0.0,
+            0.0, 1.0, 0.0, dt,
+            0.0, 0.0, 1.0, 0.0,
+            0.0, 0.0, 0.0, 1.0,
+        );
+
+        // Discrete white noise acceleration
+        let q = self.process_noise.powi(2);
+        let (dt2, dt3, dt4) = (dt.powi(2), dt.powi(3), dt.powi(4));
+        let noise = Matrix4::new(
+            dt4 / 4.0, 0.0, dt3 / 2.0, 0.0,
+            0.0, dt4 / 4.0, 0.0, dt3 / 2.0,
+            dt3 / 2.0, 0.0, dt2, 0.0,
+            0.0, dt3 / 2.0, 0.0, dt2,
+        ) * q;
+
+        self.state = transition * self.state;
+        self.covariance = transition * self.covariance * transition.transpose() + noise;
+    }
+
+    // Range measurement, linearised about the current state
+    fn correct(&mut self, x: f32, y: f32, range: f32, path_loss_exponent: f32) {
+        let (dx, dy) = (self.state[0] - x, self.state[1] - y);
+        let predicted = (dx.powi(2) + dy.powi(2)).sqrt().max(f32::EPSILON);
+
+        let jacobian = RowVector4::new(dx / predicted, dy / predicted, 0.0, 0.0);
+
+        // An RSSI error of so many dB stretches the range by a constant factor
+        let range_noise = range * std::f32::consts::LN_10 / (10.0 * path_loss_exponent) * self.rssi_noise_db;
+        let innovation_variance = (jacobian * self.covariance * jacobian.transpose())[0] + range_noise.powi(2).max(f32::EPSILON);
+
+        let gain = self.covariance * jacobian.transpose() / innovation_variance;
+
+        self.state += gain * (range - predicted);
+        self.covariance = (Matrix4::identity() - gain * jacobian) * self.covariance;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::NetInfo;
+
+    // A point measuring a network at `network` exactly as the default calculator expects
+    fn reading(x: f32, y: f32, network: (f32, f32)) -> Point {
+        let distance = ((x - network.0).powi(2) + (y - network.1).powi(2)).sqrt();
+        let measured_power = -30.0 - 10.0 * TrilaterationCalculator::default().get_path_loss_exponent() * distance.log10();
+        let net_info = NetInfo { tx_power: Some(-30.0), measured_power: Some(measured_power), samples: Vec::new() };
+
+        return Point::new(x, y, Some(net_info));
+    }
+
+    // An observer walking a 20 by 20 square, a step a second
+    fn walk(step: usize) -> (f32, f32) {
+        let along = (step % 80) as f32;
+
+        return match (step % 80) / 20 {
+            0 => (along, 0.0),
+            1 => (20.0, along - 20.0),
+            2 => (60.0 - along, 20.0),
+            _ => (0.0, 80.0 - along),
+        };
+    }
+
+    #[test]
+    fn converges_on_a_still_network() {
+        let calc = TrilaterationCalculator::default();
+        let mut tracker = Tracker::default();
+
+        // Three laps, the first one mostly spent working out which side of the path the network is
+        for step in 0..240 {
+            let (x, y) = walk(step);
+            tracker.update(step as f64, &[reading(x, y, (8.0, 12.0))], &calc);
+        }
+
+        let estimate = tracker.estimate().unwrap();
+        assert!((estimate.x - 8.0).abs() < 0.5 && (estimate.y - 12.0).abs() < 0.5, "({}, {})", estimate.x, estimate.y);
+        assert_eq!(tracker.get_track().len(), 240);
+    }
+
+    // Determinant of the position covariance after each of 20 rounds of readings from the sensors
+    fn uncertainty(sensors: &[(f32, f32)]) -> Vec<f32> {
+        let calc = TrilaterationCalculator::default();
+        let mut tracker = Tracker { process_noise: 0.0, ..Tracker::default() };
+
+        return (0..20).map(|step| {
+            let points: Vec<Point> = sensors.iter().map(|(x, y)| reading(*x, *y, (8.0, 12.0))).collect();
+            tracker.update(step as f64, &points, &calc).unwrap().covariance.determinant()
+        }).collect();
+    }
+
+    #[test]
+    fn grows_more_certain_with_more_readings() {
+        let three = uncertainty(&[(0.0, 0.0), (30.0, 0.0), (0.0, 30.0)]);
+        let four = uncertainty(&[(0.0, 0.0), (30.0, 0.0), (0.0, 30.0), (30.0, 30.0)]);
+
+        // Grows at first while the unknown starting velocity plays out, then shrinks with every round
+        assert!(four[5..].windows(2).all(|pair| pair[1] < pair[0]), "{:?}", four);
+        assert!(four[19] < four[5] / 4.0, "{:?}", four);
+
+        // And an extra sensor helps every round
+        assert!(three.iter().zip(four.iter()).all(|(three, four)| four < three), "{:?} {:?}", three, four);
+    }
+
+    #[test]
+    fn follows_a_moving_network() {
+        let calc = TrilaterationCalculator::default();
+        let mut tracker = Tracker::default();
+        let sensors = [(0.0, 0.0), (30.0, 0.0), (0.0, 30.0), (30.0, 30.0)];
+
+        // Half a unit a second along x, across the sensors
+        let network = |time: f32| (5.0 + 0.5 * time, 15.0);
+
+        for step in 0..40 {
+            let time = step as f32;
+            let points: Vec<Point> = sensors.iter().map(|(x, y)| reading(*x, *y, network(time))).collect();
+            tracker.update(f64::from(time), &points, &calc);
+        }
+
+        let (x, y) = network(39.0);
+        let estimate = tracker.estimate().unwrap();
+        assert!((estimate.x - x).abs() < 1.0 && (estimate.y - y).abs() < 1.0, "({}, {}) for ({}, {})", estimate.x, estimate.y, x, y);
+
+        // Moved as far as the network did, not stuck where it started
+        let start = &tracker.get_track()[0];
+        assert!(estimate.x - start.x > 15.0);
+    }
+
+    #[test]
+    fn excluded_points_leave_the_estimate_alone() {
+        let calc = TrilaterationCalculator::default();
+        let mut points = vec![reading(0.0, 0.0, (5.0, 4.0)), reading(10.0, 0.0, (5.0, 4.0)), reading(0.0, 10.0, (5.0, 4.0)), reading(10.0, 10.0, (5.0, 4.0))];
+
+        let mut without_outlier = Tracker::default();
+        let expected = without_outlier.update(0.0, &points, &calc).unwrap().clone();
+
+        // Reads as if it were right next to the network
+        let mut outlier = reading(20.0, 20.0, (20.0, 20.5));
+        outlier.excluded = true;
+        points.push(outlier);
+
+        let mut with_outlier = Tracker::default();
+        let estimate = with_outlier.update(0.0, &points, &calc).unwrap();
+        assert_eq!((estimate.x, estimate.y), (expected.x, expected.y));
+
+        points[4].excluded = false;
+        let mut included = Tracker::default();
+        let estimate = included.update(0.0, &points, &calc).unwrap();
+        assert_ne!((estimate.x, estimate.y), (expected.x, expected.y));
+    }
+}
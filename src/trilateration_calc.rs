@@ -1,37 +1,19 @@
-use nalgebra::{Vector3, Matrix3};
+use nalgebra::{DMatrix, DVector};
+use tracing::{debug, info_span};
 
-#[derive(Clone)] 
-pub struct NetInfo {
-    pub tx_power: Option<f32>,
-    pub measured_power: Option<f32>,
-}
-
-#[derive(Clone)] 
-pub struct Point {
-    pub x: f32,
-    pub y: f32,
-    pub net_info: Option<NetInfo>,
-}
-
-impl Point {
-    pub fn new(x: f32, y: f32, net_info: Option<NetInfo>) -> Point {
-        return Point { x, y, net_info };
-    }
-}
-
-pub struct Location {
-    pub x: f32,
-    pub y: f32,
-}
+pub use crate::models::{Location, NetInfo, Point};
+use crate::units::DistanceUnit;
 
 pub struct TrilaterationCalculator {
     path_loss_exponent: f32,
+    unit: DistanceUnit, // Unit of the points' coordinates, and of every distance handed back
 }
 
 impl Default for TrilaterationCalculator {
     fn default() -> Self {
         Self {
             path_loss_exponent: 3.0,
+            unit: DistanceUnit::Metres,
         }
     }
 }
@@ -41,46 +23,102 @@ impl TrilaterationCalculator {
         self.path_loss_exponent = path_loss_exponent;
     }
 
-    pub fn get_location(&self, point_one: &Point, point_two: &Point, point_three: &Point) -> Location {
+    pub fn get_path_loss_exponent(&self) -> f32 {
+        return self.path_loss_exponent;
+    }
+
+    pub fn set_unit(&mut self, unit: DistanceUnit) {
+        self.unit = unit;
+    }
+
+    pub fn get_unit(&self) -> DistanceUnit {
+        return self.unit;
+    }
+
+    pub fn get_location(&self, points: &[Point]) -> Option<Location> {
         // """
         // Calculates the estimated location based on the measured power in dBm and transmit power in dBm
-        // and the known positions of three test points.
+        // and the known positions of the tested points.
 
         // Args:
-        //     point_one (borrowed Point): The Point struct (Measured Power, Transmit Power, X, Y) of test point one.
-        //     point_two (borrowed Point): The Point struct (Measured Power, Transmit Power, X, Y) of test point two.
-        //     point_three (borrowed Point): The Point struct (Measured Power, Transmit Power, X, Y) of test point three.
+        //     points (borrowed Point slice): The Point structs (Measured Power, Transmit Power, X, Y, Z) of the test points.
+        //                                    Points that haven't been tested, or are excluded, are ignored.
 
         // Returns:
-        //     Location: The estimated position (X, Y)
+        //     Option<Location>: The estimated position (X, Y), or None if fewer than three tested points
+        //                       are available or they all lie on a line.
         // """
 
+        let tested_points: Vec<&Point> = points.iter().filter(|point| self.is_usable(point)).collect();
+
+        let _span = info_span!("solve", points = tested_points.len(), exponent = self.path_loss_exponent).entered();
+
+        if tested_points.len() < 3 {
+            debug!("Fewer than three tested points");
+            return None;
+        }
+
         // Trilateration
-        let estimated_location = self.trilaterate(&point_one, &point_two, &point_three);
+        let estimated_location = self.trilaterate(&tested_points);
 
         return estimated_location;
     }
 
-    fn trilaterate(&self, point_one: &Point, point_two: &Point, point_three: &Point) -> Location {
+    fn trilaterate(&self, points: &[&Point]) -> Option<Location> {
         // """
-        // Trilaterates the location (X, Y) given the distances, from the selected network, of three test points.
+        // Trilaterates the location (X, Y) given the distances, from the selected network, of the test points.
 
         // Args:
-        //     d1 (float): distance from the first point to the unknown position.
-        //     d2 (float): distance from the second point to the unknown position.
-        //     d3 (float): distance from the third point to the unknown position.
+        //     points (borrowed Point slice): The tested points, at least three.
 
         // Returns:
-        //     Location: The (X, Y) coordinates of the selected network's, unknown position.
+        //     Option<Location>: The (X, Y) coordinates of the selected network's, unknown position.
         // """
 
         // Use least squares to solve the equations
-        let results = self.calculate_location(&point_one, &point_two, &point_three, self.path_loss_exponent).unwrap();
+        let results = self.calculate_location(points, self.path_loss_exponent);
 
         // Return the estimated coordinates
-        return Location {x: results.x, y: results.y};
+        return results;
+    }
+
+    // Whether the solver will use the point, tested and not excluded
+    pub fn is_usable(&self, point: &Point) -> bool {
+        return !point.excluded && self.estimate_distance(point.net_info.as_ref()).is_some();
+    }
+
+    // Distance a point's reading puts it from the network, if the point has been tested, in the calculator's unit
+    pub fn estimate_distance(&self, net_info: Option<&NetInfo>) -> Option<f32> {
+        let network_info = net_info?;
+
+        network_info.tx_power?;
+        network_info.measured_power?;
+
+        return Some(self.unit.from_metres(self.get_distance(Some(network_info), self.path_loss_exponent)));
+    }
+
+    // Range along the floor a tested point's reading puts it from the network
+    pub fn estimate_range(&self, point: &Point) -> Option<f32> {
+        self.estimate_distance(point.net_info.as_ref())?;
+
+        return Some(self.horizontal_distance(point, self.path_loss_exponent));
+    }
+
+    // How far a location sits outside (positive) or inside (negative) a tested point's range circle
+    pub fn get_residual(&self, point: &Point, location: &Location) -> Option<f32> {
+        let range = self.estimate_range(point)?;
+
+        return Some(residual(point.x, point.y, range, location.x, location.y));
+    }
+
+    // RSSI the propagation model expects a distance (in the calculator's unit) away from a network transmitting at tx_power
+    pub fn expected_rssi(&self, tx_power: f32, distance: f32) -> f32 {
+        let metres = self.unit.to_metres(distance);
+
+        return tx_power - 10.0 * self.path_loss_exponent * metres.max(f32::EPSILON).log10();
     }
 
+    // Distance in metres, tx_power being the expected RSSI one metre from the network
     fn get_distance(&self, net_info: Option<&NetInfo>, path_loss_exponent: f32) -> f32 {
         let base: f32 = 10.0;
     
@@ -91,38 +129,110 @@ impl TrilaterationCalculator {
     
         return base.powf((tx_power - measured_power) / (10.0 * path_loss_exponent));
     }
+
+    // Distance along the floor, the point's height (z) above the network taken out of its range
+    fn horizontal_distance(&self, point: &Point, path_loss_exponent: f32) -> f32 {
+        let distance = self.unit.from_metres(self.get_distance(point.net_info.as_ref(), path_loss_exponent));
+
+        return (distance.powi(2) - point.z.powi(2)).max(0.0).sqrt();
+    }
     
     // Trilateration with Linear Least Squares
-    // System of quadratic distance equation
-    fn calculate_location(&self,p1: &Point, p2: &Point, p3: &Point, path_loss_exponent: f32) -> Option<Location> {
-        let x1 = p1.x;
-        let y1 = p1.y;
-        let r1 = self.get_distance(p1.net_info.as_ref(), path_loss_exponent);
-        let x2 = p2.x;
-        let y2 = p2.y;
-        let r2 = self.get_distance(p2.net_info.as_ref(), path_loss_exponent);
-        let x3 = p3.x;
-        let y3 = p3.y;
-        let r3 = self.get_distance(p3.net_info.as_ref(), path_loss_exponent);
-    
-        println!("d:{} d:{} d:{}", r1, r2, r3);
-    
+    // System of quadratic distance equations, each one subtracted from the first
+    fn calculate_location(&self, points: &[&Point], path_loss_exponent: f32) -> Option<Location> {
+        let ranges: Vec<(f32, f32, f32)> = points.iter().map(|point| (point.x, point.y, self.horizontal_distance(point, path_loss_exponent))).collect();
+
+        debug!(ranges = ?ranges.iter().map(|(_, _, r)| *r).collect::<Vec<f32>>(), unit = %self.unit, "Horizontal ranges to each point");
+
+        let (x1, y1, r1) = ranges[0];
+
         // Constructing the matrix system
-        let a = 2.0 * (x2 - x1);
-        let b = 2.0 * (y2 - y1);
-        let c = r1.powi(2) - r2.powi(2) - x1.powi(2) + x2.powi(2) - y1.powi(2) + y2.powi(2);
-        
-        let d = 2.0 * (x3 - x1);
-        let e = 2.0 * (y3 - y1);
-        let f = r1.powi(2) - r3.powi(2) - x1.powi(2) + x3.powi(2) - y1.powi(2) + y3.powi(2);
-    
-        let matrix = Matrix3::new(a, b, 0.0, d, e, 0.0, 0.0, 0.0, 1.0);
-        let vector = Vector3::new(c, f, 0.0);
-    
-        if let Some(solution) = matrix.try_inverse().map(|inv| inv * vector) {
-            return Some(Location{ x: solution.x, y: solution.y });
+        let rows = ranges.len() - 1;
+        let matrix = DMatrix::from_fn(rows, 2, |row, col| {
+            let (x, y, _) = ranges[row + 1];
+            if col == 0 { 2.0 * (x - x1) } else { 2.0 * (y - y1) }
+        });
+        let vector = DVector::from_fn(rows, |row, _| {
+            let (x, y, r) = ranges[row + 1];
+            r1.powi(2) - r.powi(2) - x1.powi(2) + x.powi(2) - y1.powi(2) + y.powi(2)
+        });
+
+        // Normal equations, singular when every point lies on one line
+        let normal = matrix.transpose() * &matrix;
+        let solution = normal.try_inverse().map(|inv| inv * matrix.transpose() * vector)?;
+
+        if !solution.iter().all(|value| value.is_finite()) {
+            return None;
         }
-    
-        None
+
+        // How far the solution sits from each range circle
+        let residuals: Vec<f32> = ranges.iter().map(|(x, y, r)| residual(*x, *y, *r, solution[0], solution[1])).collect();
+        let uncertainty = (residuals.iter().map(|residual| residual.powi(2)).sum::<f32>() / residuals.len() as f32).sqrt();
+
+        return Some(Location{ x: solution[0], y: solution[1], uncertainty });
+    }
+}
+
+fn residual(x: f32, y: f32, range: f32, location_x: f32, location_y: f32) -> f32 {
+    return ((location_x - x).powi(2) + (location_y - y).powi(2)).sqrt() - range;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NETWORK: (f32, f32) = (12.0, 7.0);
+
+    // A point measuring NETWORK as the calculator would expect it, off by noise dB
+    fn reading(x: f32, y: f32, noise: f32, calc: &TrilaterationCalculator) -> Point {
+        let distance = ((x - NETWORK.0).powi(2) + (y - NETWORK.1).powi(2)).sqrt();
+        let net_info = NetInfo { tx_power: Some(-30.0), measured_power: Some(calc.expected_rssi(-30.0, distance) + noise), samples: Vec::new() };
+
+        return Point::new(x, y, Some(net_info));
+    }
+
+    #[test]
+    fn solves_an_exact_survey() {
+        let calc = TrilaterationCalculator::default();
+        let points = [reading(0.0, 0.0, 0.0, &calc), reading(30.0, 0.0, 0.0, &calc), reading(0.0, 20.0, 0.0, &calc)];
+
+        let location = calc.get_location(&points).unwrap();
+        assert!((location.x - NETWORK.0).abs() < 0.01 && (location.y - NETWORK.1).abs() < 0.01, "({}, {})", location.x, location.y);
+        assert!(location.uncertainty < 0.01);
+    }
+
+    #[test]
+    fn solves_a_noisy_survey() {
+        let calc = TrilaterationCalculator::default();
+        let points = [
+            reading(0.0, 0.0, 2.0, &calc),
+            reading(30.0, 0.0, -2.0, &calc),
+            reading(0.0, 20.0, 1.5, &calc),
+            reading(30.0, 20.0, -1.0, &calc),
+            reading(15.0, 30.0, 1.0, &calc),
+        ];
+
+        let location = calc.get_location(&points).unwrap();
+        assert!((location.x - NETWORK.0).abs() < 3.0 && (location.y - NETWORK.1).abs() < 3.0, "({}, {})", location.x, location.y);
+        assert!(location.uncertainty > 0.1);
+    }
+
+    #[test]
+    fn needs_points_off_a_line() {
+        let calc = TrilaterationCalculator::default();
+        let points = [reading(0.0, 0.0, 0.0, &calc), reading(10.0, 0.0, 0.0, &calc), reading(20.0, 0.0, 0.0, &calc)];
+
+        assert!(calc.get_location(&points).is_none());
+    }
+
+    #[test]
+    fn needs_three_usable_points() {
+        let calc = TrilaterationCalculator::default();
+        let mut points = vec![reading(0.0, 0.0, 0.0, &calc), reading(30.0, 0.0, 0.0, &calc), Point::new(0.0, 20.0, None)];
+        assert!(calc.get_location(&points).is_none());
+
+        points[2] = reading(0.0, 20.0, 0.0, &calc);
+        points[2].excluded = true;
+        assert!(calc.get_location(&points).is_none());
     }
 }
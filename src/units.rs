@@ -0,0 +1,120 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+pub const METRES_PER_FOOT: f32 = 0.3048;
+
+// Unit a session's coordinates, ranges and uncertainties are in. The propagation model always
+// works in metres, since transmit power is referenced to one metre from the antenna.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DistanceUnit {
+    #[default]
+    Metres,
+    Feet,
+}
+
+impl DistanceUnit {
+    pub const ALL: [DistanceUnit; 2] = [DistanceUnit::Metres, DistanceUnit::Feet];
+
+    pub fn metres_per_unit(&self) -> f32 {
+        return match self {
+            DistanceUnit::Metres => 1.0,
+            DistanceUnit::Feet => METRES_PER_FOOT,
+        };
+    }
+
+    pub fn to_metres(&self, value: f32) -> f32 {
+        return value * self.metres_per_unit();
+    }
+
+    pub fn from_metres(&self, metres: f32) -> f32 {
+        return metres / self.metres_per_unit();
+    }
+
+    // Converts a value in this unit into `other`
+    pub fn convert(&self, value: f32, other: DistanceUnit) -> f32 {
+        return other.from_metres(self.to_metres(value));
+    }
+
+    pub fn suffix(&self) -> &'static str {
+        return match self {
+            DistanceUnit::Metres => "m",
+            DistanceUnit::Feet => "ft",
+        };
+    }
+
+    pub fn parse(text: &str) -> Option<DistanceUnit> {
+        return match text.trim().to_lowercase().as_str() {
+            "m" | "metre" | "metres" | "meter" | "meters" => Some(DistanceUnit::Metres),
+            "ft" | "foot" | "feet" => Some(DistanceUnit::Feet),
+            _ => None,
+        };
+    }
+}
+
+impl fmt::Display for DistanceUnit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            DistanceUnit::Metres => write!(f, "Metres"),
+            DistanceUnit::Feet => write!(f, "Feet"),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Location, Point, Wall};
+    use crate::session::Session;
+
+    #[test]
+    fn parses_units() {
+        let cases = [
+            ("m", Some(DistanceUnit::Metres)),
+            ("Metres", Some(DistanceUnit::Metres)),
+            (" meters ", Some(DistanceUnit::Metres)),
+            ("ft", Some(DistanceUnit::Feet)),
+            ("FEET", Some(DistanceUnit::Feet)),
+            ("foot", Some(DistanceUnit::Feet)),
+            ("", None),
+            ("yards", None),
+        ];
+
+        for (text, expected) in cases {
+            assert_eq!(DistanceUnit::parse(text), expected, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn converts_sessions_without_moving_anything() {
+        let mut point = Point::new(3.048, 6.096, None);
+        point.z = 0.3048;
+
+        let mut session = Session {
+            points: vec![point],
+            walls: vec![Wall { start: [0.0, 0.0], end: [30.48, 0.0], attenuation_db: 5.0 }],
+            location: Some(Location { x: 1.524, y: 3.048, uncertainty: 0.6096 }),
+            ..Session::default()
+        };
+
+        let cases = [
+            (DistanceUnit::Feet, [10.0, 20.0, 1.0], 100.0, [5.0, 10.0, 2.0]),
+            (DistanceUnit::Feet, [10.0, 20.0, 1.0], 100.0, [5.0, 10.0, 2.0]), // Already in feet, so nothing changes
+            (DistanceUnit::Metres, [3.048, 6.096, 0.3048], 30.48, [1.524, 3.048, 0.6096]),
+        ];
+
+        for (unit, [x, y, z], wall_end, [location_x, location_y, uncertainty]) in cases {
+            session.set_unit(unit);
+            assert_eq!(session.unit, unit);
+
+            let point = &session.points[0];
+            assert!((point.x - x).abs() < 1e-4 && (point.y - y).abs() < 1e-4 && (point.z - z).abs() < 1e-4, "{} ({}, {}, {})", unit, point.x, point.y, point.z);
+            assert!((session.walls[0].end[0] - wall_end).abs() < 1e-3, "{} {}", unit, session.walls[0].end[0]);
+            assert_eq!(session.walls[0].attenuation_db, 5.0);
+
+            let location = session.location.as_ref().unwrap();
+            assert!((location.x - location_x).abs() < 1e-4 && (location.y - location_y).abs() < 1e-4 && (location.uncertainty - uncertainty).abs() < 1e-4);
+        }
+    }
+}